@@ -1,11 +1,118 @@
-use criterion::{criterion_group, criterion_main, Criterion};
-use squid_tokenizer::tokenize;
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput,
+};
+use squid_tokenizer::{config::TokenizerConfig, tokenize, tokens};
 
-fn tokenize_benchmark(c: &mut Criterion) {
-    const FRENCH: &str =
-        "Le soleil brille, illuminant la ville endormie. Les rues sont calmes, baignées dans une douce lumière. Au loin, les oiseaux oisifs chantent la vie !";
+const FRENCH: &str =
+    "Le soleil brille, illuminant la ville endormie. Les rues sont calmes, baignées dans une douce lumière. Au loin, les oiseaux oisifs chantent la vie !";
+const TWEET: &str =
+    "🚨 L'IVG dans la Constitution ! Merci @FranceInsoumise pour le #8mars https://www.lemonde.fr/politique/ 👩🏽‍🚀";
+
+/// Tokenizer of afd0a24, copied verbatim as a baseline.
+mod baseline {
+    use std::{collections::HashSet, convert::Infallible, path::Path};
+
+    /// Lowercase words, remove punctuation, separate words into tokens and convert them into numbers.
+    pub fn tokenize<T: ToString>(text: T) -> Result<String, Infallible> {
+        stopwords::init(Path::new("./stopwords").to_path_buf());
+
+        let punctuation: HashSet<char> = ['!', ',', '.', ':', ';', '?', '-', '\"', '(', ')']
+            .iter()
+            .cloned()
+            .collect();
+
+        let result_string: String = stopwords::remove_words_from_sentence(
+            text.to_string()
+                .replace('\'', " ")
+                .to_lowercase()
+                .chars()
+                .filter(|c| !punctuation.contains(c))
+                .collect::<String>()
+                .split_ascii_whitespace()
+                .filter(|c| *c != " " && c.len() > 1)
+                .map(|c| format!("{} ", c))
+                .collect(),
+        );
+
+        let normalize = result_string
+            .chars()
+            .map(|c| {
+                if c.len_utf8() > 1 {
+                    c.escape_unicode().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<String>();
+
+        Ok(normalize.trim_end().to_string())
+    }
+
+    mod stopwords {
+        use std::{
+            fs::OpenOptions,
+            io::{BufRead, BufReader},
+            path::PathBuf,
+            sync::OnceLock,
+        };
+
+        static STOP_WORDS: OnceLock<Vec<String>> = OnceLock::new();
+
+        /// Inits `STOP_WORDS` by adding every lines from a text file
+        /// to the cache.
+        pub fn init(path: PathBuf) {
+            STOP_WORDS.get_or_init(|| {
+                if let Ok(file) = OpenOptions::new().read(true).open(path) {
+                    let reader = BufReader::new(&file);
 
+                    let mut words: Vec<String> = vec![];
+                    for word in reader.lines().map_while(Result::ok) {
+                        words.push(word)
+                    }
+
+                    words
+                } else {
+                    Vec::default()
+                }
+            });
+        }
+
+        /// Removes every stop words from a sentence.
+        pub fn remove_words_from_sentence(sentence: String) -> String {
+            let stop_words = STOP_WORDS.get_or_init(Vec::default);
+
+            sentence
+                .split_whitespace()
+                .filter(|word| !stop_words.contains(&word.to_lowercase()))
+                .collect::<Vec<&str>>()
+                .join(" ")
+        }
+    }
+}
+
+fn tokenize_benchmark(c: &mut Criterion) {
     c.bench_function("tokenize 150 bytes", |b| b.iter(|| tokenize(FRENCH)));
+
+    // Same settings as a service, built once and shared by every text.
+    let config = TokenizerConfig::default();
+    let long = FRENCH.repeat(64);
+    let texts = [("french", FRENCH), ("tweet", TWEET), ("long", long.as_str())];
+
+    let mut group = c.benchmark_group("throughput");
+    for (name, text) in texts {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("baseline", name), text, |b, text| {
+            b.iter(|| baseline::tokenize(black_box(text)))
+        });
+        group.bench_with_input(BenchmarkId::new("tokenize", name), text, |b, text| {
+            b.iter(|| tokenize(black_box(text)))
+        });
+        group.bench_with_input(BenchmarkId::new("tokens", name), text, |b, text| {
+            b.iter(|| tokens(black_box(text)).config(&config).lang("fr").count())
+        });
+    }
+    group.finish();
 }
 
 criterion_group!(benches, tokenize_benchmark);
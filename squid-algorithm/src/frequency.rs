@@ -0,0 +1,246 @@
+use ahash::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Minimum number of periods with texts to measure variations.
+const MIN_PERIODS: usize = 3;
+
+/// Texts added during a period.
+#[derive(Debug, Default, Clone)]
+struct Period {
+    /// Start of the period, as a number of periods since the Unix epoch.
+    index: u64,
+    /// Number of texts.
+    documents: usize,
+    /// Number of texts containing each word.
+    words: HashMap<String, usize, RandomState>,
+}
+
+/// Word found in many texts at a steady rate, likely a stop word.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The word.
+    pub word: String,
+    /// Share of texts containing the word, between 0 and 1.
+    pub frequency: f64,
+    /// Coefficient of variation of the share of texts containing the word
+    /// across periods. Trends vary a lot, stop words do not.
+    pub variation: f64,
+}
+
+/// Document frequency of words over recent periods of time, used to discover
+/// stop words.
+///
+/// # Example
+/// ```rust
+/// use squid_algorithm::frequency::DocumentFrequency;
+///
+/// let mut frequency = DocumentFrequency::new(3600, 24);
+/// for hour in 0..4 {
+///     frequency.add(hour * 3600, ["rt", "pluie"]);
+///     frequency.add(hour * 3600 + 60, ["rt", "soleil", "rt"]);
+/// }
+/// // A trend.
+/// for _ in 0..4 {
+///     frequency.add(3 * 3600 + 120, ["rt", "macron"]);
+/// }
+///
+/// let candidates = frequency.candidates(0.3, 0.2);
+/// assert_eq!(candidates.len(), 1);
+/// assert_eq!(candidates[0].word, "rt");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentFrequency {
+    /// Duration of a period, in seconds.
+    duration: u64,
+    /// Maximum number of periods kept.
+    length: usize,
+    /// Periods, from the oldest to the newest.
+    periods: VecDeque<Period>,
+}
+
+impl DocumentFrequency {
+    /// Creates a tracker of the last `length` periods of `duration` seconds.
+    pub fn new(duration: u64, length: usize) -> Self {
+        DocumentFrequency {
+            duration: duration.max(1),
+            length: length.max(1),
+            periods: VecDeque::new(),
+        }
+    }
+
+    /// Adds the words of a text written at a Unix timestamp, in seconds.
+    ///
+    /// Each word is counted once per text. Texts older than the newest
+    /// period are counted in it.
+    pub fn add<I, T>(&mut self, timestamp: u64, words: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let index = timestamp / self.duration;
+        if self
+            .periods
+            .back()
+            .is_none_or(|period| period.index < index)
+        {
+            self.periods.push_back(Period {
+                index,
+                ..Default::default()
+            });
+            while self.periods.len() > self.length {
+                self.periods.pop_front();
+            }
+        }
+
+        let Some(period) = self.periods.back_mut() else {
+            return;
+        };
+        period.documents += 1;
+
+        let mut seen = HashSet::new();
+        for word in words {
+            let word = word.as_ref();
+            if seen.insert(word.to_string()) {
+                match period.words.get_mut(word) {
+                    Some(count) => *count += 1,
+                    None => {
+                        period.words.insert(word.to_string(), 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the words found in at least a share `min_frequency` of texts,
+    /// whose share varies by at most `max_variation` across periods, from the
+    /// most to the least frequent.
+    ///
+    /// Nothing is returned until texts were added during three periods.
+    pub fn candidates(&self, min_frequency: f64, max_variation: f64) -> Vec<Candidate> {
+        let periods: Vec<&Period> = self
+            .periods
+            .iter()
+            .filter(|period| period.documents > 0)
+            .collect();
+        if periods.len() < MIN_PERIODS {
+            return Vec::new();
+        }
+
+        let documents: usize = periods.iter().map(|period| period.documents).sum();
+        let mut totals: HashMap<&str, usize, RandomState> = HashMap::default();
+        for period in &periods {
+            for (word, count) in &period.words {
+                *totals.entry(word.as_str()).or_default() += count;
+            }
+        }
+
+        let mut candidates: Vec<Candidate> = totals
+            .into_iter()
+            .map(|(word, count)| (word, count as f64 / documents as f64))
+            .filter(|(_, frequency)| *frequency >= min_frequency)
+            .map(|(word, frequency)| {
+                let shares: Vec<f64> = periods
+                    .iter()
+                    .map(|period| {
+                        period.words.get(word).copied().unwrap_or_default() as f64
+                            / period.documents as f64
+                    })
+                    .collect();
+                let mean = shares.iter().sum::<f64>() / shares.len() as f64;
+                let variance = shares
+                    .iter()
+                    .map(|share| (share - mean).powi(2))
+                    .sum::<f64>()
+                    / shares.len() as f64;
+
+                Candidate {
+                    word: word.to_string(),
+                    frequency,
+                    variation: variance.sqrt() / mean,
+                }
+            })
+            .filter(|candidate| candidate.variation <= max_variation)
+            .collect();
+
+        candidates.sort_by(|a, b| {
+            b.frequency
+                .total_cmp(&a.frequency)
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        candidates
+    }
+
+    /// Forgets words, once they are stop words.
+    pub fn remove<T: AsRef<str>>(&mut self, word: T) {
+        for period in &mut self.periods {
+            period.words.remove(word.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds the same texts during `periods` hours.
+    fn steady(periods: u64) -> DocumentFrequency {
+        let mut frequency = DocumentFrequency::new(3600, 24);
+        for hour in 0..periods {
+            frequency.add(hour * 3600, ["rt", "pluie"]);
+            frequency.add(hour * 3600 + 60, ["rt", "soleil"]);
+        }
+        frequency
+    }
+
+    #[test]
+    fn test_min_periods() {
+        assert!(steady(MIN_PERIODS as u64 - 1)
+            .candidates(0.0, f64::MAX)
+            .is_empty());
+
+        let candidates = steady(MIN_PERIODS as u64).candidates(0.9, 0.0);
+        assert_eq!(
+            candidates,
+            vec![Candidate {
+                word: "rt".to_string(),
+                frequency: 1.0,
+                variation: 0.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_trend() {
+        let mut frequency = steady(4);
+        // Found in every text of the last period only.
+        for _ in 0..8 {
+            frequency.add(3 * 3600 + 120, ["rt", "macron"]);
+        }
+
+        let words = |max_variation| -> Vec<String> {
+            frequency
+                .candidates(0.3, max_variation)
+                .into_iter()
+                .map(|candidate| candidate.word)
+                .collect()
+        };
+        assert_eq!(words(0.5), vec!["rt"]);
+        assert_eq!(words(f64::MAX), vec!["rt", "macron"]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut frequency = steady(4);
+        frequency.remove("rt");
+
+        let candidates = frequency.candidates(0.5, 0.0);
+        assert!(candidates.iter().all(|candidate| candidate.word != "rt"));
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|candidate| candidate.word.as_str())
+                .collect::<Vec<_>>(),
+            vec!["pluie", "soleil"]
+        );
+    }
+}
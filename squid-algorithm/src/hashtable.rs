@@ -1,52 +1,435 @@
 use ahash::RandomState;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::{cmp::Reverse, collections::HashMap};
+
+/// Maximum number of spellings kept for each key. The least frequent one
+/// makes room for a new one.
+const MAX_FORMS: usize = 8;
+
+/// What a counted key represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// Any other word.
+    Word,
+    /// Word starting with `#`.
+    Hashtag,
+    /// User name starting with `@`.
+    Mention,
+    /// Link, or its domain name.
+    Url,
+    /// E-mail address, or its domain name.
+    Email,
+    /// Pictogram such as 🚨.
+    Emoji,
+    /// Number, ordinal or quantity.
+    Number,
+    /// Day of a month or date.
+    Date,
+    /// Proper-noun phrase, such as "Aya Nakamura".
+    Phrase,
+}
+
+/// Occurrences of a key, with what it represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counter {
+    /// Number of occurrences.
+    pub count: usize,
+    /// Kind of the token, as given when the key was first added.
+    pub kind: Kind,
+    /// Whether any occurrence of the key is an abusive term.
+    pub flagged: bool,
+}
+
+/// An occurrence of a key in a text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence<'a> {
+    /// Spelling of the key in the original text, if known.
+    pub display: Option<&'a str>,
+    /// Kind of the token.
+    pub kind: Kind,
+    /// Whether the token is an abusive term.
+    pub flagged: bool,
+}
 
 /// Structure containing the data required by the HashMap algorithm.
 #[derive(Debug, Default, Clone)]
 pub struct MapAlgorithm {
     /// Data from the HashMap.
-    data: HashMap<String, usize, RandomState>,
+    data: HashMap<String, Counter, RandomState>,
+    /// Spellings of each key in the original texts, with their occurrences.
+    forms: HashMap<String, HashMap<String, usize, RandomState>, RandomState>,
+    /// Canonical key of merged spelling variants.
+    aliases: HashMap<String, String, RandomState>,
+    /// Occurrences of each merged spelling variant counted under its
+    /// canonical key, to subtract them if the variant is deleted.
+    merged: HashMap<String, usize, RandomState>,
 }
 
 impl MapAlgorithm {
-    /// Adds data to the data contained in the HashMap.
+    /// Adds a word to the data contained in the HashMap.
     pub fn set<T>(&mut self, key: T)
     where
         T: ToString,
     {
+        self.add(
+            key,
+            Occurrence {
+                display: None,
+                kind: Kind::Word,
+                flagged: false,
+            },
+        );
+    }
+
+    /// Adds an occurrence of a key to the data contained in the HashMap.
+    pub fn add<T>(&mut self, key: T, occurrence: Occurrence)
+    where
+        T: ToString,
+    {
+        let key = key.to_string();
+        let key = match self.aliases.get(&key) {
+            Some(canonical) => {
+                let canonical = canonical.clone();
+                *self.merged.entry(key).or_default() += 1;
+                canonical
+            }
+            None => key,
+        };
+
+        if let Some(display) = occurrence.display {
+            let forms = self.forms.entry(key.clone()).or_default();
+            match forms.get_mut(display) {
+                Some(count) => *count += 1,
+                None => {
+                    if forms.len() >= MAX_FORMS {
+                        let rarest = forms
+                            .iter()
+                            .min_by(|(a, a_count), (b, b_count)| {
+                                a_count.cmp(b_count).then_with(|| b.cmp(a))
+                            })
+                            .map(|(form, _)| form.clone());
+                        if let Some(rarest) = rarest {
+                            forms.remove(&rarest);
+                        }
+                    }
+                    forms.insert(display.to_string(), 1);
+                }
+            }
+        }
+
         self.data
-            .entry(key.to_string())
-            .and_modify(|d| *d += 1)
-            .or_insert(1);
+            .entry(key)
+            .and_modify(|counter| {
+                counter.count += 1;
+                counter.flagged |= occurrence.flagged;
+            })
+            .or_insert(Counter {
+                count: 1,
+                kind: occurrence.kind,
+                flagged: occurrence.flagged,
+            });
     }
 
-    /// Removes data from the data contained in the HashMap.
-    pub fn remove<T>(&mut self, key: T)
+    /// Returns the most frequent spelling of a key, if any was added with
+    /// [`MapAlgorithm::add`].
+    ///
+    /// Ties are broken alphabetically, so the result does not change between
+    /// calls.
+    pub fn display(&self, key: &str) -> Option<&str> {
+        self.forms.get(key).and_then(|forms| {
+            forms
+                .iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+                .map(|(form, _)| form.as_str())
+        })
+    }
+
+    /// Removes data from the data contained in the HashMap, written as
+    /// `display` in the original text if it is known.
+    ///
+    /// # Example
+    /// ```rust
+    /// use squid_algorithm::hashtable::{Kind, MapAlgorithm, Occurrence};
+    ///
+    /// let mut map = MapAlgorithm::default();
+    /// for display in ["IVG", "IVG", "ivg"] {
+    ///     let kind = Kind::Word;
+    ///     map.add("ivg", Occurrence { display: Some(display), kind, flagged: false });
+    /// }
+    ///
+    /// map.remove("ivg", Some("IVG"));
+    /// map.remove("ivg", Some("IVG"));
+    /// assert_eq!(map.display("ivg"), Some("ivg"));
+    /// ```
+    pub fn remove<T>(&mut self, key: T, display: Option<&str>)
     where
         T: ToString,
     {
-        if let Some(count) = self.data.get_mut(&key.to_string()) {
-            if *count > 1 {
-                *count -= 1;
+        let key = key.to_string();
+        if let Some(count) = self.merged.get_mut(&key) {
+            *count = count.saturating_sub(1);
+        }
+        let key = self.resolve(key);
+        if let Some(counter) = self.data.get_mut(&key) {
+            if counter.count > 1 {
+                counter.count -= 1;
             } else {
-                self.data.remove(&key.to_string());
+                self.data.remove(&key);
+                self.forms.remove(&key);
+                return;
+            }
+        }
+
+        if let Some((forms, display)) = self.forms.get_mut(&key).zip(display) {
+            match forms.get_mut(display) {
+                Some(count) if *count > 1 => *count -= 1,
+                Some(_) => {
+                    forms.remove(display);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Removes every occurrence of a key.
+    ///
+    /// Deleting a merged spelling variant subtracts its occurrences from its
+    /// canonical key, and stops counting it as the canonical key. Its
+    /// spellings stay among those of the canonical key.
+    ///
+    /// # Example
+    /// ```rust
+    /// use squid_algorithm::hashtable::MapAlgorithm;
+    ///
+    /// let mut map = MapAlgorithm::default();
+    /// map.set("mbappé");
+    /// map.set("mbappe");
+    ///
+    /// map.merge("mbappe", "mbappé");
+    /// map.delete("mbappe");
+    ///
+    /// assert_eq!(map.rank(2), vec![("mbappé".to_string(), 1)]);
+    /// ```
+    pub fn delete(&mut self, key: &str) {
+        if let Some(canonical) = self.aliases.remove(key) {
+            let count = self.merged.remove(key).unwrap_or_default();
+            if let Some(counter) = self.data.get_mut(&canonical) {
+                if counter.count > count {
+                    counter.count -= count;
+                } else {
+                    self.data.remove(&canonical);
+                    self.forms.remove(&canonical);
+                }
+            }
+            return;
+        }
+
+        self.data.remove(key);
+        self.forms.remove(key);
+        self.aliases.retain(|_, canonical| canonical != key);
+        self.merged
+            .retain(|variant, _| self.aliases.contains_key(variant));
+    }
+
+    /// Returns a copy of the count of each key, to look for spelling
+    /// variants with [`crate::variants::find`] without holding the map.
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        self.data
+            .iter()
+            .map(|(key, counter)| (key.clone(), counter.count))
+            .collect()
+    }
+
+    /// Returns merged keys, with their canonical key.
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(variant, canonical)| (variant.as_str(), canonical.as_str()))
+    }
+
+    /// Counts a key as another one, now and when it is added again.
+    ///
+    /// Occurrences and spellings of the variant are moved to the canonical
+    /// key, which keeps its [`MAX_FORMS`] most frequent spellings.
+    ///
+    /// # Example
+    /// ```rust
+    /// use squid_algorithm::hashtable::MapAlgorithm;
+    ///
+    /// let mut map = MapAlgorithm::default();
+    /// map.set("mbappé");
+    /// map.set("mbappé");
+    /// map.set("mbappe");
+    ///
+    /// map.merge("mbappe", "mbappé");
+    /// map.set("mbappe");
+    ///
+    /// assert_eq!(map.rank(2), vec![("mbappé".to_string(), 4)]);
+    /// ```
+    pub fn merge(&mut self, variant: &str, canonical: &str) {
+        let canonical = self.resolve(canonical.to_string());
+        if canonical == variant {
+            return;
+        }
+
+        if let Some(counter) = self.data.remove(variant) {
+            // Occurrences of keys merged into the variant are already
+            // recorded.
+            let merged: usize = self
+                .aliases
+                .iter()
+                .filter(|(_, target)| *target == variant)
+                .filter_map(|(key, _)| self.merged.get(key))
+                .sum();
+            *self.merged.entry(variant.to_string()).or_default() +=
+                counter.count.saturating_sub(merged);
+
+            self.data
+                .entry(canonical.clone())
+                .and_modify(|merged| {
+                    merged.count += counter.count;
+                    merged.flagged |= counter.flagged;
+                })
+                .or_insert(counter);
+        }
+        if let Some(forms) = self.forms.remove(variant) {
+            let merged = self.forms.entry(canonical.clone()).or_default();
+            for (form, count) in forms {
+                *merged.entry(form).or_default() += count;
+            }
+
+            // Only the most frequent spellings are kept, as when added.
+            if merged.len() > MAX_FORMS {
+                let mut forms: Vec<(String, usize)> = merged.drain().collect();
+                forms.sort_by(|(a, a_count), (b, b_count)| {
+                    b_count.cmp(a_count).then_with(|| a.cmp(b))
+                });
+                merged.extend(forms.into_iter().take(MAX_FORMS));
             }
         }
+
+        // Keys previously merged into the variant follow it.
+        for target in self.aliases.values_mut() {
+            if target == variant {
+                *target = canonical.clone();
+            }
+        }
+        self.aliases.insert(variant.to_string(), canonical);
+    }
+
+    /// Returns the canonical key of a key.
+    fn resolve(&self, key: String) -> String {
+        match self.aliases.get(&key) {
+            Some(canonical) => canonical.clone(),
+            None => key,
+        }
     }
 
     /// Classify the most frequently used words.
     pub fn rank(&self, length: usize) -> Vec<(String, usize)> {
-        let mut sorted_word_counts: Vec<_> =
-            self.data.clone().into_iter().collect();
-        sorted_word_counts.sort_by(|a, b| b.1.cmp(&a.1));
-
-        let most_used_words: Vec<_> = sorted_word_counts
-            .par_iter()
-            .take(length)
-            .map(|(word, count)| (word.clone(), *count))
+        self.rank_by(length, |_, _| true)
+    }
+
+    /// Classify the most frequently used words matching a filter.
+    ///
+    /// # Example
+    /// ```rust
+    /// use squid_algorithm::hashtable::{Kind, MapAlgorithm, Occurrence};
+    ///
+    /// let mut map = MapAlgorithm::default();
+    /// map.set("gravitalia");
+    /// map.add(
+    ///     "gravitalia.com",
+    ///     Occurrence { display: None, kind: Kind::Email, flagged: false },
+    /// );
+    ///
+    /// let emails = map.rank_by(2, |_, counter| counter.kind == Kind::Email);
+    /// assert_eq!(emails, vec![("gravitalia.com".to_string(), 1)]);
+    /// ```
+    pub fn rank_by<F>(&self, length: usize, filter: F) -> Vec<(String, usize)>
+    where
+        F: Fn(&str, &Counter) -> bool,
+    {
+        let mut sorted_word_counts: Vec<_> = self
+            .data
+            .iter()
+            .filter(|(word, counter)| filter(word, counter))
+            .map(|(word, counter)| (word.clone(), counter.count))
             .collect();
+        sorted_word_counts.sort_by_key(|(_, count)| Reverse(*count));
+
+        sorted_word_counts.into_par_iter().take(length).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds a key once for each of its spellings.
+    fn add(map: &mut MapAlgorithm, key: &str, displays: &[&str]) {
+        for display in displays {
+            map.add(
+                key,
+                Occurrence {
+                    display: Some(display),
+                    kind: Kind::Word,
+                    flagged: false,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_forms() {
+        let mut map = MapAlgorithm::default();
+        let canonical: Vec<String> = (0..MAX_FORMS).map(|i| format!("Mbappé{}", i)).collect();
+        let canonical: Vec<&str> = canonical.iter().map(String::as_str).collect();
+        add(&mut map, "mbappé", &canonical);
+        add(&mut map, "mbappé", &["MBAPPÉ", "MBAPPÉ"]);
+        add(
+            &mut map,
+            "mbappe",
+            &["MBAPPE", "MBAPPE", "MBAPPE", "Mbappe"],
+        );
+
+        map.merge("mbappe", "mbappé");
+
+        let forms = &map.forms["mbappé"];
+        assert_eq!(forms.len(), MAX_FORMS);
+        assert_eq!(forms.get("MBAPPE"), Some(&3));
+        assert_eq!(forms.get("MBAPPÉ"), Some(&2));
+        // Ties are broken alphabetically.
+        assert!(forms.contains_key("Mbappe") && !forms.contains_key("Mbappé6"));
+        assert_eq!(map.display("mbappé"), Some("MBAPPE"));
+    }
+
+    #[test]
+    fn test_delete_merged() {
+        let mut map = MapAlgorithm::default();
+        add(&mut map, "mbappé", &["Mbappé"; 10]);
+        add(&mut map, "mbape", &["Mbape"; 2]);
+        add(&mut map, "mbappe", &["Mbappe"; 3]);
+
+        // Variants of a variant follow it, and are subtracted once.
+        map.merge("mbape", "mbappe");
+        map.merge("mbappe", "mbappé");
+        add(&mut map, "mbape", &["Mbape"]);
+        map.remove("mbappe", None);
+        assert_eq!(map.rank(1), vec![("mbappé".to_string(), 15)]);
+
+        map.delete("mbappe");
+        assert_eq!(map.rank(1), vec![("mbappé".to_string(), 13)]);
+        map.delete("mbape");
+        assert_eq!(map.rank(1), vec![("mbappé".to_string(), 10)]);
+        assert_eq!(map.aliases().count(), 0);
+
+        // Deleted variants are counted on their own again.
+        add(&mut map, "mbappe", &["Mbappe"]);
+        assert_eq!(map.rank(2).len(), 2);
 
-        most_used_words
+        map.merge("mbappe", "mbappé");
+        map.delete("mbappé");
+        assert!(map.rank(2).is_empty() && map.merged.is_empty());
     }
 }
@@ -7,5 +7,9 @@
 #![forbid(unsafe_code)]
 #![deny(dead_code, unused_imports, unused_mut, missing_docs)]
 
+/// Document frequency of words over time, to discover stop words.
+pub mod frequency;
 /// The most accurate algorithm for ranking.
 pub mod hashtable;
+/// Detection of spelling variants of counted words.
+pub mod variants;
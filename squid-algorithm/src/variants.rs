@@ -0,0 +1,251 @@
+use ahash::RandomState;
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound of [`Options::max_distance`]. Each word is compared through
+/// its deletions, whose number grows as its length to the power of the
+/// distance.
+pub const MAX_DISTANCE: usize = 2;
+
+/// Guards used to find spelling variants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// Maximum number of edits (insertions, deletions or substitutions of a
+    /// character) between a variant and its canonical form, up to
+    /// [`MAX_DISTANCE`].
+    pub max_distance: usize,
+    /// Minimum number of characters of words. Short words often differ by a
+    /// single letter, as "jour" and "tour".
+    pub min_length: usize,
+    /// Maximum number of characters of words. Longer tokens are seldom
+    /// misspelled words, and cost more to compare.
+    pub max_length: usize,
+    /// Minimum ratio between occurrences of the canonical form and of the
+    /// variant. Two common words are distinct words, not variants.
+    pub min_ratio: f64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_distance: 1,
+            min_length: 5,
+            max_length: 32,
+            min_ratio: 5.0,
+        }
+    }
+}
+
+/// Rare spelling of a word, to count as its canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// The rare spelling.
+    pub variant: String,
+    /// The most frequent spelling.
+    pub canonical: String,
+    /// Number of edits between both spellings.
+    pub distance: usize,
+}
+
+/// Finds spelling variants among counted words, such as "mbape" for
+/// "mbappé".
+///
+/// Each variant is merged into the most frequent word within
+/// [`Options::max_distance`] edits. Words containing digits or symbols, such
+/// as numbers or links, are ignored, as well as hashtags or mentions compared
+/// with plain words.
+///
+/// # Example
+/// ```rust
+/// use squid_algorithm::variants::{find, Options};
+///
+/// let counts = [("mbappé", 120), ("mbappe", 20), ("mbapé", 4), ("macron", 80)];
+/// let merges = find(counts, &Options::default());
+///
+/// assert_eq!(merges.len(), 2);
+/// assert!(merges.iter().all(|merge| merge.canonical == "mbappé"));
+/// ```
+pub fn find<I, T>(counts: I, options: &Options) -> Vec<Merge>
+where
+    I: IntoIterator<Item = (T, usize)>,
+    T: AsRef<str>,
+{
+    let max_distance = options.max_distance.min(MAX_DISTANCE);
+    let mut words: Vec<(Vec<char>, usize)> = counts
+        .into_iter()
+        // Characters take up to 4 bytes: longer texts are not collected.
+        .filter(|(word, _)| word.as_ref().len() <= options.max_length * 4)
+        .map(|(word, count)| (word.as_ref().chars().collect::<Vec<_>>(), count))
+        .filter(|(word, _)| {
+            (options.min_length..=options.max_length).contains(&word.len()) && is_spelled(word)
+        })
+        .collect();
+    words.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+
+    // Words sharing a deletion are within twice the distance of each other.
+    // Deletions are indexed by their hash, since a word has hundreds of them
+    // at a distance of two: a collision only adds a word to compare.
+    let state = RandomState::new();
+    let mut index: HashMap<u64, Vec<usize>, RandomState> = HashMap::default();
+    for (position, (word, _)) in words.iter().enumerate() {
+        for deletion in deletions(word, max_distance, &state) {
+            index.entry(deletion).or_default().push(position);
+        }
+    }
+
+    let mut merged = vec![false; words.len()];
+    let mut merges = Vec::new();
+
+    for (position, (word, count)) in words.iter().enumerate() {
+        let mut neighbours: Vec<usize> = deletions(word, max_distance, &state)
+            .iter()
+            .filter_map(|deletion| index.get(deletion))
+            .flatten()
+            .copied()
+            // Only more frequent words, sorted first, can be canonical.
+            .filter(|neighbour| *neighbour < position && !merged[*neighbour])
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+
+        let canonical = neighbours.into_iter().find_map(|neighbour| {
+            let (canonical, canonical_count) = &words[neighbour];
+            let distance = distance(canonical, word);
+
+            (*canonical_count as f64 >= *count as f64 * options.min_ratio
+                && distance <= max_distance
+                && canonical.first().filter(|c| !c.is_alphabetic())
+                    == word.first().filter(|c| !c.is_alphabetic()))
+            .then_some((neighbour, distance))
+        });
+
+        if let Some((neighbour, distance)) = canonical {
+            merged[position] = true;
+            merges.push(Merge {
+                variant: word.iter().collect(),
+                canonical: words[neighbour].0.iter().collect(),
+                distance,
+            });
+        }
+    }
+
+    merges
+}
+
+/// Levenshtein distance between two words.
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Whether a word is only made of letters, after a `#` or `@`.
+fn is_spelled(word: &[char]) -> bool {
+    let letters = match word.first() {
+        Some('#' | '@') => &word[1..],
+        _ => word,
+    };
+
+    letters
+        .iter()
+        .all(|c| c.is_alphabetic() || *c == '-' || *c == '\'')
+}
+
+/// Returns the hashes of a word with up to `length` characters removed,
+/// including itself.
+fn deletions(word: &[char], length: usize, state: &RandomState) -> HashSet<u64> {
+    let mut deletions = HashSet::from([word.to_vec()]);
+    let mut last = vec![word.to_vec()];
+
+    for _ in 0..length {
+        let mut next = Vec::new();
+        for word in &last {
+            for position in 0..word.len() {
+                let mut deletion = word.clone();
+                deletion.remove(position);
+                if deletions.insert(deletion.clone()) {
+                    next.push(deletion);
+                }
+            }
+        }
+        last = next;
+    }
+
+    deletions
+        .iter()
+        .map(|deletion| state.hash_one(deletion))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance(&chars("mbappé"), &chars("mbappé")), 0);
+        assert_eq!(distance(&chars("mbappé"), &chars("mbappe")), 1);
+        assert_eq!(distance(&chars("mbappé"), &chars("mbapé")), 1);
+        assert_eq!(distance(&chars("macron"), &chars("marcon")), 2);
+        assert_eq!(distance(&chars(""), &chars("rt")), 2);
+    }
+
+    #[test]
+    fn test_is_spelled() {
+        assert!(is_spelled(&chars("aujourd'hui")));
+        assert!(is_spelled(&chars("#mbappé")));
+        assert!(is_spelled(&chars("@jean-luc")));
+        assert!(!is_spelled(&chars("covid19")));
+        assert!(!is_spelled(&chars("lemonde.fr")));
+        assert!(!is_spelled(&chars("a#b")));
+    }
+
+    #[test]
+    fn test_min_ratio() {
+        let counts = [("mbappé", 40), ("mbappe", 10)];
+        assert!(find(counts, &Options::default()).is_empty());
+
+        let options = Options {
+            min_ratio: 4.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            find(counts, &options),
+            vec![Merge {
+                variant: "mbappe".to_string(),
+                canonical: "mbappé".to_string(),
+                distance: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_guards() {
+        let options = Options {
+            max_distance: 10,
+            ..Default::default()
+        };
+        // Three edits apart, over the maximum distance.
+        assert!(find([("macronie", 100), ("macro", 1)], &options).is_empty());
+        assert_eq!(find([("macronie", 100), ("macron", 1)], &options).len(), 1);
+
+        let options = Options {
+            max_length: 8,
+            ..Default::default()
+        };
+        assert!(find([("manifestation", 100), ("manifestaton", 1)], &options).is_empty());
+    }
+}
@@ -7,6 +7,7 @@
 #[cfg(feature = "compress")]
 mod compress;
 mod manager;
+mod record;
 mod ttl;
 
 pub use manager::Instance;
@@ -17,12 +18,14 @@ use squid_error::{Error, ErrorType, IoError};
 use std::{
     collections::BTreeMap,
     fs::{create_dir, read_dir, File, OpenOptions},
-    io::{self, BufRead, BufReader},
+    io::{self, Write},
     marker::PhantomData,
     path::{Path, PathBuf},
     sync::Arc,
 };
 use tokio::sync::{mpsc::Sender, RwLock};
+#[cfg(feature = "logging")]
+use tracing::warn;
 
 const SOURCE_DIRECTORY: &str = "./data/";
 const FILE_EXT: &str = "bin";
@@ -39,6 +42,17 @@ pub trait Attributes {
     fn ttl(&self) -> Option<u64> {
         None
     }
+
+    /// Decodes an entry saved with a previous layout of the type, when it
+    /// cannot be decoded with the current one.
+    ///
+    /// Files holding such entries are rewritten with the current layout.
+    fn upgrade(_record: &[u8]) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 /// [`Builder`] handle database creation.
@@ -59,6 +73,8 @@ pub struct Builder<
     sender: Option<Sender<T>>,
     /// Is TTL manager is enabled.
     ttl: bool,
+    /// Directory holding the data files.
+    directory: Option<PathBuf>,
     phantom: PhantomData<T>,
 }
 
@@ -107,6 +123,14 @@ where
         self
     }
 
+    /// Set the directory holding the data files.
+    ///
+    /// Defaults to `./data/`. It is created if missing.
+    pub fn directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
     /// Build [`squid_db::manager::Instance`].
     ///
     /// # Examples
@@ -134,30 +158,25 @@ where
     pub async fn build(
         self,
     ) -> Result<Arc<RwLock<manager::Instance<T>>>, Error> {
-        let (entires, index, file, mut file_name) = load::<T>()?;
-
-        let file = file.unwrap_or_else(|| {
-            file_name = uuid::Uuid::new_v4().to_string();
-            let path = PathBuf::from(SOURCE_DIRECTORY)
-                .join(format!("{}.{}", file_name, FILE_EXT));
-
-            OpenOptions::new()
-                .read(true)
-                .append(true)
-                .create(true)
-                .open(&path)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "failed to create new file on {}",
-                        path.to_string_lossy()
-                    )
-                })
-        });
+        let directory = self
+            .directory
+            .unwrap_or_else(|| PathBuf::from(SOURCE_DIRECTORY));
+        let (entires, index, file) = load::<T>(&directory)?;
+
+        let (file, file_name, records) = match file {
+            Some(file) => file,
+            None => {
+                let file_name = uuid::Uuid::new_v4().to_string();
+                (create(&directory, &file_name)?, file_name, 0)
+            },
+        };
 
         let instance = Arc::new(RwLock::new(manager::Instance {
+            directory,
             file,
             file_name,
             index,
+            records,
             ttl: None,
             entries: entires.0,
             memtable: Vec::new(),
@@ -183,9 +202,41 @@ where
     }
 }
 
+/// Path of a data file from its name, with or without extension.
+pub(crate) fn path(directory: &Path, name: &str) -> PathBuf {
+    if name.ends_with(FILE_EXT) {
+        directory.join(name)
+    } else {
+        directory.join(format!("{}.{}", name, FILE_EXT))
+    }
+}
+
+/// Creates a data file, starting with the header of the format.
+pub(crate) fn create(directory: &Path, name: &str) -> Result<File, Error> {
+    let path = path(directory, name);
+    OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(&record::HEADER).map(|_| file))
+        .map_err(|error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::WritingError),
+                Some(Box::new(error)),
+                Some(format!("cannot create {}", path.to_string_lossy())),
+            )
+        })
+}
+
 /// Loads a specific data file rather than the whole set.
+///
+/// Files written before records were framed, holding entries decoded with
+/// [`Attributes::upgrade`] or records altered on disk are rewritten in the
+/// current format, and a record truncated by a crash at the end of the file
+/// is dropped.
 #[inline(always)]
-fn load_file<T>(mut name: String) -> Result<World<T>, Error>
+fn load_file<T>(directory: &Path, name: String) -> Result<World<T>, Error>
 where
     T: serde::Serialize
         + serde::de::DeserializeOwned
@@ -194,45 +245,98 @@ where
         + std::marker::Sync
         + 'static,
 {
-    if !name.ends_with(FILE_EXT) {
-        name = format!("{}.{}", name, FILE_EXT);
-    }
-
-    let file = OpenOptions::new()
-        .read(true)
-        .append(true)
-        .open(Path::new(SOURCE_DIRECTORY).join(name))
-        .map_err(|error| {
-            Error::new(
-                ErrorType::Unspecified,
-                Some(Box::new(error)),
-                Some("while opening file".to_string()),
-            )
+    let path = path(directory, &name);
+    let file = File::open(&path).map_err(|error| {
+        Error::new(
+            ErrorType::Unspecified,
+            Some(Box::new(error)),
+            Some("while opening file".to_string()),
+        )
+    })?;
+
+    let contents =
+        record::read(&file).map_err(|mut error| {
+            error.context = Some(format!(
+                "{} of {}",
+                error.context.unwrap_or_default(),
+                path.to_string_lossy()
+            ));
+            error
         })?;
+    let mut world: World<T> = World(Vec::with_capacity(contents.records.len()));
+    let mut upgraded = false;
+
+    for record in &contents.records {
+        match bincode::deserialize::<T>(record) {
+            Ok(data) => world.0.push(data),
+            Err(error) => match T::upgrade(record) {
+                Some(data) => {
+                    world.0.push(data);
+                    upgraded = true;
+                },
+                // Legacy entries containing a line break were split when
+                // saved.
+                None if contents.legacy => {
+                    #[cfg(feature = "logging")]
+                    warn!(
+                        file = name,
+                        "Skipped unreadable legacy entry: {}", error
+                    );
+                },
+                None => {
+                    return Err(Error::new(
+                        ErrorType::InputOutput(IoError::DeserializationError),
+                        Some(Box::new(error)),
+                        Some(
+                            "cannot deserialize record to read file"
+                                .to_string(),
+                        ),
+                    ))
+                },
+            },
+        }
+    }
 
-    let reader = BufReader::new(&file);
-    let mut world: World<T> = World(Vec::new());
+    #[cfg(feature = "logging")]
+    for range in &contents.skipped {
+        warn!(
+            file = name,
+            "Skipped invalid record from byte {} to {}.", range.start, range.end
+        );
+    }
 
-    for line in reader.lines() {
-        let line_data: T = bincode::deserialize(
-            line.map_err(|error| {
+    if contents.legacy || upgraded || !contents.skipped.is_empty() {
+        let records = world
+            .0
+            .iter()
+            .map(bincode::serialize)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| {
                 Error::new(
-                    ErrorType::InputOutput(IoError::ReadingError),
+                    ErrorType::InputOutput(IoError::SerializationError),
                     Some(Box::new(error)),
-                    Some("cannot read line before deserialization".to_string()),
+                    Some("cannot serialize record to rewrite file".to_string()),
                 )
-            })?
-            .as_bytes(),
-        )
-        .map_err(|error| {
-            Error::new(
-                ErrorType::InputOutput(IoError::DeserializationError),
-                Some(Box::new(error)),
-                Some("cannot serialize to read file".to_string()),
-            )
-        })?;
+            })?;
+        record::rewrite(&path, records.iter().map(Vec::as_slice))?;
+
+        #[cfg(feature = "logging")]
+        warn!(file = name, "Rewrote file in the current format.");
+    } else if let Some(length) = contents.truncated_at {
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_len(length))
+            .map_err(|error| {
+                Error::new(
+                    ErrorType::InputOutput(IoError::WritingError),
+                    Some(Box::new(error)),
+                    Some(format!("cannot truncate {}", path.to_string_lossy())),
+                )
+            })?;
 
-        world.0.push(line_data);
+        #[cfg(feature = "logging")]
+        warn!(file = name, "Dropped truncated record at byte {}.", length);
     }
 
     Ok(world)
@@ -240,10 +344,20 @@ where
 
 /// Reads data from each saved file in the source directory,
 /// generates an index, and returns any unfinished files
-/// (those with fewer than the specified maximum entries).
+/// (those with fewer than the specified maximum entries),
+/// with their name and their number of records.
 #[inline(always)]
+#[allow(clippy::type_complexity)]
 fn load<T>(
-) -> Result<(World<T>, BTreeMap<String, String>, Option<File>, String), Error>
+    directory: &Path,
+) -> Result<
+    (
+        World<T>,
+        BTreeMap<String, String>,
+        Option<(File, String, usize)>,
+    ),
+    Error,
+>
 where
     T: serde::Serialize
         + serde::de::DeserializeOwned
@@ -254,12 +368,11 @@ where
 {
     let mut world: World<T> = World(Vec::new());
     let mut index: BTreeMap<String, String> = BTreeMap::new();
-    let mut uncomplete_file: Option<File> = None;
-    let mut file_name = String::default();
+    let mut uncomplete_file: Option<(File, String, usize)> = None;
 
-    let _ = create_dir(SOURCE_DIRECTORY);
+    let _ = create_dir(directory);
 
-    for entry in read_dir(SOURCE_DIRECTORY)
+    for entry in read_dir(directory)
         .map_err(|error| {
             Error::new(
                 ErrorType::InputOutput(IoError::WritingError),
@@ -277,31 +390,34 @@ where
         })?
     {
         let filename = entry.file_name().into_string().unwrap_or_default();
-        let mut data: Vec<T> = load_file(filename.to_string())?.0;
+        // Skips files left by an interrupted rewrite.
+        if Path::new(&filename).extension() != Some(FILE_EXT.as_ref()) {
+            continue;
+        }
+
+        let mut data: Vec<T> = load_file(directory, filename.to_string())?.0;
 
         for line in &data {
             index.insert(line.id(), filename.clone());
         }
 
         if data.len() < MAX_ENTRIES_PER_FILE {
-            uncomplete_file = Some(
-                OpenOptions::new()
-                    .read(true)
-                    .append(true)
-                    .open(&Path::new(SOURCE_DIRECTORY).join(filename))
-                    .map_err(|error| {
-                        Error::new(
-                            ErrorType::Unspecified,
-                            Some(Box::new(error)),
-                            Some("while opening file to load it".to_string()),
-                        )
-                    })?,
-            );
-            file_name = entry.file_name().into_string().unwrap_or_default();
+            let file = OpenOptions::new()
+                .read(true)
+                .append(true)
+                .open(path(directory, &filename))
+                .map_err(|error| {
+                    Error::new(
+                        ErrorType::Unspecified,
+                        Some(Box::new(error)),
+                        Some("while opening file to load it".to_string()),
+                    )
+                })?;
+            uncomplete_file = Some((file, filename, data.len()));
         }
 
         world.0.append(&mut data);
     }
 
-    Ok((world, index, uncomplete_file, file_name))
+    Ok((world, index, uncomplete_file))
 }
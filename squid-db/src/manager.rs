@@ -1,22 +1,20 @@
 //! database manager.
 //! supports read, write, memtable.
 
-use crate::{
-    ttl::TTL, Attributes, FILE_EXT, MAX_ENTRIES_PER_FILE, SOURCE_DIRECTORY,
-};
+use crate::{record, ttl::TTL, Attributes, MAX_ENTRIES_PER_FILE};
 use serde::Serialize;
 use squid_error::{Error, ErrorType, IoError};
 use std::{
     collections::BTreeMap,
     fs::{File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
+    io::Write,
     marker::PhantomData,
     path::PathBuf,
     sync::Arc,
 };
 use tokio::sync::{mpsc::Sender, RwLock};
 #[cfg(feature = "logging")]
-use tracing::trace;
+use tracing::{trace, warn};
 
 /// Structure representing the database world.
 #[derive(Serialize, PartialEq, Debug)]
@@ -40,6 +38,8 @@ pub struct Instance<
         + std::marker::Sync
         + 'static,
 > {
+    /// Directory holding the data files.
+    pub(super) directory: PathBuf,
     /// File writing new entries.
     /// There is no need to re-open the file each time.
     pub(super) file: File,
@@ -48,6 +48,8 @@ pub struct Instance<
     /// Index to link an ID to a file.
     /// This allows the file to be targeted for modification or deletion.
     pub(super) index: BTreeMap<String, String>,
+    /// Number of records in the opened file.
+    pub(super) records: usize,
     /// TTL manager.
     pub(super) ttl: Option<Arc<RwLock<TTL<T>>>>,
     /// Data saved on disk.
@@ -75,7 +77,9 @@ where
     /// Get entry from its unique identifier.
     pub fn get(&self, id: String) -> Result<Option<T>, Error> {
         if let Some(file_name) = self.index.get(&id) {
-            let data = crate::load_file::<T>(file_name.to_string())?.0;
+            let data =
+                crate::load_file::<T>(&self.directory, file_name.to_string())?
+                    .0;
 
             Ok(data.into_iter().find(|entry| entry.id() == id))
         } else {
@@ -140,8 +144,10 @@ where
                     )
                 })?;
 
-                self.index.insert(data.id(), self.file_name.clone());
-                self.save(&encoded)?
+                // The file changes once the entry fills it.
+                let file_name = self.file_name.clone();
+                self.save(&encoded)?;
+                self.index.insert(data.id(), file_name);
             },
             max_kb_size => {
                 self.memtable.push(data);
@@ -165,54 +171,55 @@ where
 
     /// Deletes a record from the data based on its unique identifier.
     pub fn delete(&mut self, id: &str) -> Result<(), Error> {
-        if let Some(file_name) = self.index.get(id) {
-            let file =
-                File::open(PathBuf::from(SOURCE_DIRECTORY).join(file_name))
-                    .map_err(|error| {
-                        Error::new(
-                            ErrorType::InputOutput(IoError::ReadingError),
-                            Some(Box::new(error)),
-                            Some(
-                                "cannot open file to delete entry".to_string(),
-                            ),
-                        )
-                    })?;
-            let reader = BufReader::new(file);
-
-            let lines: Vec<Vec<u8>> = reader
-                .lines()
-                .map_while(Result::ok)
-                .map(|entry| entry.as_bytes().to_vec())
-                .collect();
+        if let Some(file_name) = self.index.get(id).cloned() {
+            let path = crate::path(&self.directory, &file_name);
+            let file = File::open(&path).map_err(|error| {
+                Error::new(
+                    ErrorType::InputOutput(IoError::ReadingError),
+                    Some(Box::new(error)),
+                    Some("cannot open file to delete entry".to_string()),
+                )
+            })?;
+            let records = record::read(file)?.records;
 
-            let index_to_delete = lines.iter().position(|line| {
-                if let Ok(data) = bincode::deserialize::<T>(line) {
+            let index_to_delete = records.iter().position(|record| {
+                if let Ok(data) = bincode::deserialize::<T>(record) {
                     return data.id() == id;
                 }
                 false
             });
 
             if let Some(index) = index_to_delete {
-                let mut file = OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .open(PathBuf::from(SOURCE_DIRECTORY).join(file_name))
-                    .map_err(|error| {
-                        Error::new(
-                            ErrorType::Unspecified,
-                            Some(Box::new(error)),
-                            Some(
-                                "during file opening to delete row".to_string(),
-                            ),
-                        )
-                    })?;
+                // The file is replaced rather than truncated, so an error
+                // while writing it leaves the previous one intact.
+                record::rewrite(
+                    &path,
+                    records
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != index)
+                        .map(|(_, record)| record.as_slice()),
+                )?;
+
+                if crate::path(&self.directory, &self.file_name) == path {
+                    self.file = OpenOptions::new()
+                        .read(true)
+                        .append(true)
+                        .open(&path)
+                        .map_err(|error| {
+                            Error::new(
+                                ErrorType::Unspecified,
+                                Some(Box::new(error)),
+                                Some(
+                                    "cannot reopen file after deleting row"
+                                        .to_string(),
+                                ),
+                            )
+                        })?;
+                    self.records -= 1;
+                }
 
-                lines.iter().enumerate().for_each(|(i, line)| {
-                    if i != index {
-                        writeln!(file, "{}", String::from_utf8_lossy(line))
-                            .unwrap_or_default();
-                    }
-                });
+                self.index.remove(id);
 
                 #[cfg(feature = "logging")]
                 trace!(id = id, file = file_name, "Entry deleted.",);
@@ -228,171 +235,205 @@ where
     #[inline(always)]
     #[allow(unused)]
     fn save(&mut self, buf: &[u8]) -> Result<(), Error> {
-        let mut line_count = io::BufReader::new(&self.file).lines().count();
         let mut buffer: Vec<u8> = vec![];
 
-        buffer.extend_from_slice(buf);
-        buffer.extend_from_slice(b"\n");
+        record::write(&mut buffer, buf)?;
+        self.write(&buffer)?;
 
-        self.file.write_all(&buffer).map_err(|error| {
-            Error::new(
-                ErrorType::Unspecified,
-                Some(Box::new(error)),
-                Some("saving context".to_string()),
-            )
-        })?;
-
-        if line_count + 1 >= MAX_ENTRIES_PER_FILE {
-            self.file_name = uuid::Uuid::new_v4().to_string();
-            let path = PathBuf::from(SOURCE_DIRECTORY)
-                .join(format!("{}.{}", self.file_name, FILE_EXT));
-
-            self.file = OpenOptions::new()
-                .read(true)
-                .append(true)
-                .create(true)
-                .open(&path)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "failed to create new file on {}",
-                        path.to_string_lossy()
-                    )
-                });
+        self.records += 1;
+        if self.records >= MAX_ENTRIES_PER_FILE {
+            self.rotate()?;
         }
 
         Ok(())
     }
 
     /// Saves the data contained in the buffer to the hard disk.
+    ///
+    /// Entries go to new files once the opened one is full. They are kept in
+    /// the buffer until they are written, so a failed flush can be retried.
     pub fn flush(&mut self) -> Result<(), Error> {
-        let line_count = io::BufReader::new(&self.file).lines().count();
+        while !self.memtable.is_empty() {
+            // Also retries a rotation which failed after a previous write.
+            if self.records >= MAX_ENTRIES_PER_FILE {
+                self.rotate()?;
+            }
 
-        if line_count + self.memtable.len() > MAX_ENTRIES_PER_FILE {
-            // If we just write all, number of lines will exceed maximum allowed.
-            // So, we will split into two different files.
-            let mut buffer: Vec<u8> = Vec::with_capacity(self.memtable.len());
+            let count =
+                self.memtable.len().min(MAX_ENTRIES_PER_FILE - self.records);
+            let mut buffer: Vec<u8> = Vec::new();
 
-            let mut file_limit = MAX_ENTRIES_PER_FILE - line_count;
-            for n in 0..file_limit {
-                let data = &self.memtable[n];
+            for data in &self.memtable[..count] {
+                let encoded = bincode::serialize(&data).map_err(|error| {
+                    Error::new(
+                        ErrorType::InputOutput(IoError::SerializationError),
+                        Some(Box::new(error)),
+                        Some("cannot serialize to flush database".to_string()),
+                    )
+                })?;
+                record::write(&mut buffer, &encoded)?;
+            }
 
-                buffer.extend_from_slice(&bincode::serialize(&data).map_err(
-                    |error| {
-                        Error::new(
-                            ErrorType::InputOutput(IoError::SerializationError),
-                            Some(Box::new(error)),
-                            Some(
-                                "cannot serialize to flush database"
-                                    .to_string(),
-                            ),
-                        )
-                    },
-                )?);
-                buffer.extend_from_slice(b"\n");
+            self.write(&buffer)?;
 
-                // Insert new hard entry into index.
+            // Insert new hard entries into index.
+            for data in self.memtable.drain(..count) {
                 self.index.insert(data.id(), self.file_name.clone());
             }
+            self.records += count;
+        }
 
-            self.file.write_all(&buffer).map_err(|error| {
-                Error::new(
-                    ErrorType::Unspecified,
-                    Some(Box::new(error)),
-                    Some("flush writing".to_string()),
-                )
-            })?;
-            self.file.flush().map_err(|error| {
-                Error::new(
-                    ErrorType::Unspecified,
-                    Some(Box::new(error)),
-                    Some("re-flush on flush over flush".to_string()),
-                )
-            })?;
+        Ok(())
+    }
 
-            self.file_name = uuid::Uuid::new_v4().to_string();
-            let path = PathBuf::from(SOURCE_DIRECTORY)
-                .join(format!("{}.{}", self.file_name, FILE_EXT));
-
-            self.file = OpenOptions::new()
-                .read(true)
-                .append(true)
-                .create(true)
-                .open(&path)
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "failed to create new file on {}",
-                        path.to_string_lossy()
-                    )
-                });
+    /// Writes encoded records to the opened file.
+    ///
+    /// On failure, the file is cut back to its previous length, so records
+    /// written next do not follow a partial one.
+    fn write(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        let writing_error = |error| {
+            Error::new(
+                ErrorType::InputOutput(IoError::WritingError),
+                Some(Box::new(error)),
+                Some("flush writing".to_string()),
+            )
+        };
+
+        let length = self.file.metadata().map_err(writing_error)?.len();
+        self.file
+            .write_all(buffer)
+            .and_then(|_| self.file.flush())
+            .map_err(|error| {
+                if let Err(_error) = self.file.set_len(length) {
+                    #[cfg(feature = "logging")]
+                    warn!(
+                        file = self.file_name,
+                        "Partial record left at byte {}: {}", length, _error
+                    );
+                }
+                writing_error(error)
+            })
+    }
 
-            for _ in
-                1..(line_count + self.memtable.len() - MAX_ENTRIES_PER_FILE)
-            {
-                file_limit += 1;
-                let data = &self.memtable[file_limit];
+    /// Opens a new file for the next entries.
+    fn rotate(&mut self) -> Result<(), Error> {
+        self.file_name = uuid::Uuid::new_v4().to_string();
+        self.file = crate::create(&self.directory, &self.file_name)?;
+        self.records = 0;
 
-                buffer.extend_from_slice(&bincode::serialize(&data).map_err(
-                    |error| {
-                        Error::new(
-                            ErrorType::InputOutput(IoError::SerializationError),
-                            Some(Box::new(error)),
-                            Some(
-                                "cannot serialize to flush database"
-                                    .to_string(),
-                            ),
-                        )
-                    },
-                )?);
-                buffer.extend_from_slice(b"\n");
+        Ok(())
+    }
 
-                // Insert new hard entry into index.
-                self.index.insert(data.id(), self.file_name.clone());
-            }
+    pub(super) fn ttl(&mut self, ttl: Arc<RwLock<TTL<T>>>) {
+        self.ttl = Some(ttl);
+    }
+}
 
-            self.file.write_all(&buffer).map_err(|error| {
-                Error::new(
-                    ErrorType::Unspecified,
-                    Some(Box::new(error)),
-                    Some("flush writing".to_string()),
-                )
-            })?;
-        } else {
-            let mut buffer: Vec<u8> = Vec::with_capacity(self.memtable.len());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+    use serde::Deserialize;
 
-            for data in &self.memtable {
-                buffer.extend_from_slice(&bincode::serialize(&data).map_err(
-                    |error| {
-                        Error::new(
-                            ErrorType::InputOutput(IoError::SerializationError),
-                            Some(Box::new(error)),
-                            Some(
-                                "cannot serialize to flush database"
-                                    .to_string(),
-                            ),
-                        )
-                    },
-                )?);
-                buffer.extend_from_slice(b"\n");
+    #[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+    struct Entry {
+        id: String,
+        data: Vec<u8>,
+    }
 
-                // Insert new hard entry into index.
-                self.index.insert(data.id(), self.file_name.clone());
-            }
+    impl Attributes for Entry {
+        fn id(&self) -> String {
+            self.id.clone()
+        }
+    }
 
-            self.file.write_all(&buffer).map_err(|error| {
-                Error::new(
-                    ErrorType::Unspecified,
-                    Some(Box::new(error)),
-                    Some("again flush writing".to_string()),
-                )
-            })?;
+    #[tokio::test]
+    async fn test_flush_reload_delete() {
+        let id = uuid::Uuid::new_v4().to_string();
+        let entry = || Entry {
+            id: id.clone(),
+            data: vec![0x0A, b'\n', 0xFF, 0x00],
+        };
+
+        let directory = std::env::temp_dir()
+            .join(format!("squid-db-{}", uuid::Uuid::new_v4()));
+        let instance = Builder::<Entry>::default()
+            .directory(&directory)
+            .memtable_flush_size(1_000)
+            .build()
+            .await
+            .unwrap();
+        instance.write().await.set(entry()).await.unwrap();
+        instance.write().await.flush().unwrap();
+
+        let reloaded = Builder::<Entry>::default()
+            .directory(&directory)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(
+            reloaded.read().await.get(id.clone()).unwrap(),
+            Some(entry())
+        );
+
+        reloaded.write().await.delete(&id).unwrap();
+        let reloaded = Builder::<Entry>::default()
+            .directory(&directory)
+            .build()
+            .await
+            .unwrap();
+        let entries = &reloaded.read().await.entries;
+        assert!(entries.iter().all(|entry| entry.id != id));
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
 
-            self.memtable.clear();
+    #[tokio::test]
+    async fn test_reload_corrupted() {
+        let directory = std::env::temp_dir()
+            .join(format!("squid-db-{}", uuid::Uuid::new_v4()));
+        let instance = Builder::<Entry>::default()
+            .directory(&directory)
+            .build()
+            .await
+            .unwrap();
+        for id in ["first", "second", "third"] {
+            instance
+                .write()
+                .await
+                .set(Entry {
+                    id: id.to_string(),
+                    data: vec![0x0A],
+                })
+                .await
+                .unwrap();
         }
 
-        Ok(())
-    }
-    
-    pub(super) fn ttl(&mut self, ttl: Arc<RwLock<TTL<T>>>) {
-        self.ttl = Some(ttl);
+        let path = crate::path(&directory, &instance.read().await.file_name);
+        let mut bytes = std::fs::read(&path).unwrap();
+        let second = bytes
+            .windows(6)
+            .position(|window| window == b"second")
+            .unwrap();
+        bytes[second] ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        for _ in 0..2 {
+            let reloaded = Builder::<Entry>::default()
+                .directory(&directory)
+                .build()
+                .await
+                .unwrap();
+            let ids: Vec<_> = reloaded
+                .read()
+                .await
+                .entries
+                .iter()
+                .map(Entry::id)
+                .collect();
+            assert_eq!(ids, vec!["first", "third"]);
+        }
+
+        let _ = std::fs::remove_dir_all(&directory);
     }
 }
@@ -0,0 +1,267 @@
+//! framing of records in data files.
+//!
+//! A data file starts with [`HEADER`], then each record is written as its
+//! length and its CRC32 checksum, both as little-endian `u32`, followed by
+//! its bytes. Records may then contain any byte, including line breaks, and a
+//! record altered on disk is skipped when reading, up to the next valid
+//! record.
+//!
+//! Files without header were written before records were framed, with
+//! records separated by line breaks. They are still read, to be rewritten.
+
+use squid_error::{DatabaseError, Error, ErrorType, IoError};
+use std::{
+    fs::{rename, OpenOptions},
+    io::{Read, Write},
+    ops::Range,
+    path::Path,
+};
+
+/// Start of data files: a magic number followed by the version of the format.
+pub(crate) const HEADER: [u8; 5] = *b"SQDB\x01";
+/// Size of the header of a record, in bytes.
+const RECORD_HEADER_SIZE: usize = 8;
+/// Maximum size of a record, in bytes.
+/// Larger lengths can only come from a corrupted header.
+const MAX_RECORD_SIZE: usize = 64 * 1024 * 1024;
+
+/// Records read from a data file.
+#[derive(Debug, Default)]
+pub(crate) struct Contents {
+    /// Records, in the order they were written.
+    pub(crate) records: Vec<Vec<u8>>,
+    /// Whether the file has no header, as written before records were
+    /// framed. It must be rewritten before appending records.
+    pub(crate) legacy: bool,
+    /// Length of the valid start of the file, if it ends with a truncated
+    /// record, such as after a crash while writing.
+    pub(crate) truncated_at: Option<u64>,
+    /// Byte ranges of the file skipped since they do not hold valid records,
+    /// such as records altered on disk.
+    pub(crate) skipped: Vec<Range<u64>>,
+}
+
+/// Appends a framed record to a buffer.
+///
+/// Records are not empty, since zeroed bytes, left at the end of a file by a
+/// crash, would read as empty records.
+pub(crate) fn write(buffer: &mut Vec<u8>, record: &[u8]) -> Result<(), Error> {
+    if record.is_empty() {
+        return Err(Error::new(
+            ErrorType::InputOutput(IoError::SerializationError),
+            None,
+            Some("empty record".to_string()),
+        ));
+    }
+    if record.len() > MAX_RECORD_SIZE {
+        return Err(Error::new(
+            ErrorType::InputOutput(IoError::SerializationError),
+            None,
+            Some(format!(
+                "record of {} bytes, over the limit of {} bytes",
+                record.len(),
+                MAX_RECORD_SIZE
+            )),
+        ));
+    }
+
+    buffer.reserve(RECORD_HEADER_SIZE + record.len());
+    buffer.extend_from_slice(&(record.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&crc32fast::hash(record).to_le_bytes());
+    buffer.extend_from_slice(record);
+
+    Ok(())
+}
+
+/// Reads every record of a data file, checking their length and checksum.
+///
+/// An invalid record is skipped up to the next valid one, and reported in
+/// [`Contents::skipped`]. If no valid record follows, as when the last record
+/// was truncated, the end of the file is left out and reported in
+/// [`Contents::truncated_at`]. Returns [`DatabaseError::UnsupportedVersion`]
+/// if the file was written by a newer version.
+pub(crate) fn read<R: Read>(mut reader: R) -> Result<Contents, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).map_err(|error| {
+        Error::new(
+            ErrorType::InputOutput(IoError::ReadingError),
+            Some(Box::new(error)),
+            Some("cannot read records".to_string()),
+        )
+    })?;
+
+    if data.len() >= HEADER.len() && data[..4] == HEADER[..4] {
+        if data[4] != HEADER[4] {
+            return Err(Error::new(
+                ErrorType::Database(DatabaseError::UnsupportedVersion),
+                None,
+                Some(format!("data file of version {}", data[4])),
+            ));
+        }
+    } else if HEADER.starts_with(&data) {
+        // Empty file, or header cut while creating the file.
+        return Ok(Contents {
+            legacy: true,
+            ..Default::default()
+        });
+    } else {
+        return Ok(legacy(&data));
+    }
+
+    let mut contents = Contents::default();
+    let mut offset = HEADER.len();
+
+    while offset < data.len() {
+        if let Some(record) = frame(&data, offset) {
+            offset += RECORD_HEADER_SIZE + record.len();
+            contents.records.push(record.to_vec());
+            continue;
+        }
+
+        match (offset + 1..data.len()).find(|start| frame(&data, *start).is_some())
+        {
+            Some(next) => {
+                contents.skipped.push(offset as u64..next as u64);
+                offset = next;
+            },
+            None => {
+                contents.truncated_at = Some(offset as u64);
+                break;
+            },
+        }
+    }
+
+    Ok(contents)
+}
+
+/// Writes records to a new data file, then replaces the file with it.
+pub(crate) fn rewrite<'a, I>(path: &Path, records: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut buffer = HEADER.to_vec();
+    for record in records {
+        write(&mut buffer, record)?;
+    }
+
+    let writing_error = |error| {
+        Error::new(
+            ErrorType::InputOutput(IoError::WritingError),
+            Some(Box::new(error)),
+            Some(format!("cannot rewrite {}", path.to_string_lossy())),
+        )
+    };
+
+    let temporary = path.with_extension("tmp");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temporary)
+        .map_err(writing_error)?;
+    file.write_all(&buffer).map_err(writing_error)?;
+    file.sync_all().map_err(writing_error)?;
+
+    rename(&temporary, path).map_err(writing_error)
+}
+
+/// Reads records separated by line breaks, as written before records were
+/// framed.
+fn legacy(data: &[u8]) -> Contents {
+    Contents {
+        records: data
+            .split(|byte| *byte == b'\n')
+            .filter(|record| !record.is_empty())
+            .map(<[u8]>::to_vec)
+            .collect(),
+        legacy: true,
+        ..Default::default()
+    }
+}
+
+/// Returns the record framed at a byte offset, if it is not empty and its
+/// length and checksum are valid.
+fn frame(data: &[u8], offset: usize) -> Option<&[u8]> {
+    let header = data.get(offset..offset + RECORD_HEADER_SIZE)?;
+    let (length, checksum) = header.split_at(4);
+    let length = u32::from_le_bytes(length.try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(checksum.try_into().ok()?);
+    if length == 0 || length > MAX_RECORD_SIZE {
+        return None;
+    }
+
+    let start = offset + RECORD_HEADER_SIZE;
+    data.get(start..start + length)
+        .filter(|record| crc32fast::hash(record) == checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let mut buffer = HEADER.to_vec();
+        write(&mut buffer, b"first\nrecord").unwrap();
+        write(&mut buffer, &[0xFF, 0x0A, 0x00]).unwrap();
+
+        let contents = read(buffer.as_slice()).unwrap();
+        assert_eq!(
+            contents.records,
+            vec![b"first\nrecord".to_vec(), vec![0xFF, 0x0A, 0x00]]
+        );
+        assert!(!contents.legacy && contents.truncated_at.is_none());
+
+        // Cut while writing the last record.
+        let contents = read(&buffer[..buffer.len() - 1]).unwrap();
+        assert_eq!(contents.records, vec![b"first\nrecord".to_vec()]);
+        assert_eq!(contents.truncated_at, Some(25));
+
+        // Zeroed after a crash.
+        let mut zeroed = buffer.clone();
+        zeroed.extend_from_slice(&[0; 12]);
+        let contents = read(zeroed.as_slice()).unwrap();
+        assert_eq!(contents.records.len(), 2);
+        assert_eq!(contents.truncated_at, Some(buffer.len() as u64));
+
+        let error = read(&b"SQDB\x09"[..]).unwrap_err();
+        assert!(matches!(
+            error.etype,
+            ErrorType::Database(DatabaseError::UnsupportedVersion)
+        ));
+    }
+
+    #[test]
+    fn test_corrupted_records() {
+        let mut buffer = HEADER.to_vec();
+        for record in [&b"first"[..], b"second", b"third", b"fourth"] {
+            write(&mut buffer, record).unwrap();
+        }
+
+        // Altered data of "second", and length of "third".
+        let (second, third) = (18, 32);
+        buffer[second + RECORD_HEADER_SIZE] ^= 1;
+        buffer[third] = 0xFF;
+
+        let contents = read(buffer.as_slice()).unwrap();
+        assert_eq!(
+            contents.records,
+            vec![b"first".to_vec(), b"fourth".to_vec()]
+        );
+        assert_eq!(contents.skipped, vec![18..45]);
+        assert!(contents.truncated_at.is_none());
+    }
+
+    #[test]
+    fn test_legacy() {
+        let contents = read(&b"first\nsecond\n"[..]).unwrap();
+        assert!(contents.legacy);
+        assert_eq!(
+            contents.records,
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+
+        let contents = read(&b""[..]).unwrap();
+        assert!(contents.legacy && contents.records.is_empty());
+    }
+}
@@ -66,6 +66,8 @@ pub enum ErrorType {
     Database(DatabaseError),
     /// IO errors, especially due to std::fs.
     InputOutput(IoError),
+    /// Errors related to `squid-tokenizer`.
+    Tokenizer(TokenizerError),
 }
 
 impl fmt::Display for ErrorType {
@@ -76,6 +78,7 @@ impl fmt::Display for ErrorType {
             },
             ErrorType::Database(error) => write!(f, "{:?}", error),
             ErrorType::InputOutput(error) => write!(f, "{:?}", error),
+            ErrorType::Tokenizer(error) => write!(f, "{}", error),
         }
     }
 }
@@ -86,6 +89,8 @@ impl StdError for ErrorType {}
 pub enum DatabaseError {
     /// File compression failed.
     FailedCompression,
+    /// A data file was written with an unknown version of the format.
+    UnsupportedVersion,
 }
 
 impl fmt::Display for DatabaseError {
@@ -94,6 +99,9 @@ impl fmt::Display for DatabaseError {
             DatabaseError::FailedCompression => {
                 write!(f, "File compression failed.")
             },
+            DatabaseError::UnsupportedVersion => {
+                write!(f, "Data file format version is not supported.")
+            },
         }
     }
 }
@@ -130,3 +138,27 @@ impl fmt::Display for IoError {
     }
 }
 impl StdError for IoError {}
+
+/// Errors related to `squid-tokenizer`, due to invalid input texts.
+#[derive(Debug)]
+pub enum TokenizerError {
+    /// No token is left after processing.
+    Empty,
+    /// Text is longer than the allowed number of bytes.
+    TooLong,
+    /// Text contains control characters, such as binary data.
+    InvalidCharacter,
+}
+
+impl fmt::Display for TokenizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenizerError::Empty => write!(f, "No word left after processing."),
+            TokenizerError::TooLong => write!(f, "Text is too long."),
+            TokenizerError::InvalidCharacter => {
+                write!(f, "Text contains invalid control characters.")
+            },
+        }
+    }
+}
+impl StdError for TokenizerError {}
@@ -0,0 +1,122 @@
+//! settings of the tokenization pipeline.
+//!
+//! A [`TokenizerConfig`] is usually built once per service and shared by
+//! every text, through [`Tokens::config`](crate::Tokens::config).
+
+use crate::{link::Policy, number, transliterate::Scheme};
+use std::path::PathBuf;
+
+/// Maximum size of a text by default, in bytes.
+pub const MAX_BYTES: usize = 64 * 1024;
+
+/// Characters removed from words by default.
+pub const PUNCTUATION: [char; 10] = ['!', ',', '.', ':', ';', '?', '-', '\"', '(', ')'];
+
+/// What to do with apostrophes (`'` and `’`) inside words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Apostrophe {
+    /// Removes elided words and expands contractions, so "l'IVG" gives
+    /// "ivg" and "don't" gives "do" and "not". See [`elision`](crate::elision).
+    #[default]
+    Elide,
+    /// Splits words, so "l'IVG" gives "l" and "ivg".
+    Split,
+    /// Removes them, so "aujourd'hui" gives "aujourdhui".
+    Remove,
+    /// Keeps them, so "aujourd'hui" stays whole.
+    Keep,
+}
+
+/// Settings of the tokenization pipeline.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::{config::TokenizerConfig, tokens};
+///
+/// let config = TokenizerConfig {
+///     min_length: 3,
+///     stem: true,
+///     ..Default::default()
+/// };
+/// let tokens: Vec<_> = tokens("Manifestations JO")
+///     .config(&config)
+///     .lang("fr")
+///     .map(|token| token.text)
+///     .collect();
+///
+/// assert_eq!(tokens, vec!["manifest"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenizerConfig {
+    /// Maximum size of a text, in bytes. See [`check`](crate::check).
+    pub max_bytes: usize,
+    /// Characters removed from words.
+    pub punctuation: Vec<char>,
+    /// Minimum number of characters of a token. Emoji are always kept.
+    pub min_length: usize,
+    /// What to do with apostrophes inside words.
+    pub apostrophe: Apostrophe,
+    /// Custom stop words file, with one word per line, to register for the
+    /// service in the [`stopwords::registry`](crate::stopwords::registry).
+    /// It is not read by the tokenizer itself, and is [`None`] by default.
+    pub stopwords: Option<PathBuf>,
+    /// Removes stop words.
+    pub remove_stopwords: bool,
+    /// Collapses elongated letters. See
+    /// [`Tokens::collapse_repeats`](crate::Tokens::collapse_repeats).
+    pub collapse_repeats: bool,
+    /// Replaces leetspeak. See [`Tokens::fold_leet`](crate::Tokens::fold_leet).
+    pub fold_leet: bool,
+    /// Flags abusive terms. See
+    /// [`Tokens::flag_profanity`](crate::Tokens::flag_profanity).
+    pub flag_profanity: bool,
+    /// Removes diacritics. See [`Tokens::fold_accents`](crate::Tokens::fold_accents).
+    pub fold_accents: bool,
+    /// Transliterates Cyrillic and Greek to Latin script. See
+    /// [`Tokens::transliterate`](crate::Tokens::transliterate).
+    pub transliterate: Option<Scheme>,
+    /// Replaces words by their lemma. See [`Tokens::lemmatize`](crate::Tokens::lemmatize).
+    pub lemmatize: bool,
+    /// Reduces words to their stem. See [`Tokens::stem`](crate::Tokens::stem).
+    pub stem: bool,
+    /// Also emits the words of hashtags. See
+    /// [`Tokens::segment_hashtags`](crate::Tokens::segment_hashtags).
+    pub segment_hashtags: bool,
+    /// Also emits proper-noun phrases. See [`Tokens::phrases`](crate::Tokens::phrases).
+    pub phrases: bool,
+    /// What to do with numbers and dates. See
+    /// [`Tokens::numbers`](crate::Tokens::numbers).
+    pub numbers: number::Policy,
+    /// What to do with URLs.
+    pub urls: Policy,
+    /// What to do with mentions.
+    pub mentions: Policy,
+    /// What to do with e-mail addresses.
+    pub emails: Policy,
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        TokenizerConfig {
+            max_bytes: MAX_BYTES,
+            punctuation: PUNCTUATION.to_vec(),
+            min_length: 2,
+            apostrophe: Apostrophe::default(),
+            stopwords: None,
+            remove_stopwords: true,
+            collapse_repeats: false,
+            fold_leet: false,
+            flag_profanity: false,
+            fold_accents: false,
+            transliterate: None,
+            lemmatize: false,
+            stem: false,
+            segment_hashtags: false,
+            phrases: false,
+            numbers: number::Policy::default(),
+            urls: Policy::default(),
+            mentions: Policy::default(),
+            emails: Policy::default(),
+        }
+    }
+}
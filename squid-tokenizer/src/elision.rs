@@ -0,0 +1,161 @@
+//! handling of elisions and contractions.
+//!
+//! Elided articles and pronouns are removed, so "l'IVG" and "d'IVG" count
+//! for "IVG". English contractions are expanded, so "don't" gives "do" and
+//! "not". Other apostrophes, as in "aujourd'hui", are kept. Both the
+//! typewriter (`'`) and the typographic (`’`) apostrophes are handled.
+
+use std::ops::Range;
+
+/// Characters used as apostrophes.
+pub const APOSTROPHES: [char; 2] = ['\'', '’'];
+
+/// Elided words before an apostrophe, by ISO 639-1 language code.
+const ELISIONS: [(&str, &[&str]); 3] = [
+    ("ca", &["d", "l", "m", "n", "s", "t"]),
+    (
+        "fr",
+        &[
+            "c", "d", "j", "jusqu", "l", "lorsqu", "m", "n", "puisqu", "qu", "quoiqu", "s", "t",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "all", "c", "d", "dall", "dell", "l", "nell", "quell", "quest", "s", "sull", "un",
+        ],
+    ),
+];
+
+/// English contracted words after an apostrophe, which are removed.
+const CONTRACTIONS: [&str; 6] = ["d", "ll", "m", "re", "s", "ve"];
+
+/// English words changing before "n't", as in "can't".
+const NEGATIONS: [(&str, &str); 3] = [("ca", "can"), ("sha", "shall"), ("wo", "will")];
+
+/// Part of a word, after elisions and contractions are handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piece {
+    /// Position of the piece in the word.
+    pub range: Range<usize>,
+    /// Full form of the piece, if it differs from the text, as "not" for
+    /// "n't".
+    pub expansion: Option<&'static str>,
+}
+
+/// Whether a character is an apostrophe.
+pub fn is_apostrophe(c: char) -> bool {
+    APOSTROPHES.contains(&c)
+}
+
+/// Splits a word on its elisions and contractions, into at most two pieces.
+///
+/// The language, as an ISO 639-1 code, selects the rules to apply. All of
+/// them are applied if it is empty or unknown.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::elision::split;
+///
+/// let word = "jusqu’aujourd'hui";
+/// let pieces: Vec<&str> = split(word, "fr")
+///     .map(|piece| &word[piece.range])
+///     .collect();
+///
+/// assert_eq!(pieces, vec!["aujourd'hui"]);
+/// ```
+pub fn split(word: &str, lang: &str) -> impl Iterator<Item = Piece> {
+    let trimmed = word.trim_start_matches(APOSTROPHES);
+    let mut start = word.len() - trimmed.len();
+    let mut end = start + trimmed.trim_end_matches(APOSTROPHES).len();
+
+    let known = |code: &str| {
+        lang.is_empty()
+            || lang == code
+            || !(lang == "en" || ELISIONS.iter().any(|(code, _)| *code == lang))
+    };
+
+    // Elided article or pronoun, such as "l'".
+    if let Some(index) = word[start..end].find(APOSTROPHES) {
+        let head = word[start..start + index]
+            .trim_start_matches(|c: char| !c.is_alphanumeric() && c != '#' && c != '@');
+        let elided = ELISIONS.iter().any(|(code, words)| {
+            known(code) && words.iter().any(|elided| elided.eq_ignore_ascii_case(head))
+        });
+
+        if elided {
+            start += index
+                + word[start + index..]
+                    .chars()
+                    .next()
+                    .map_or(0, char::len_utf8);
+        }
+    }
+
+    // English contraction, such as "'re" or "n't".
+    if let Some(index) = word[start..end].rfind(APOSTROPHES).filter(|_| known("en")) {
+        let apostrophe = start + index;
+        let head = &word[start..apostrophe];
+        let tail = word[apostrophe..end]
+            .trim_start_matches(APOSTROPHES)
+            .trim_end_matches(|c: char| !c.is_alphanumeric());
+
+        if tail.eq_ignore_ascii_case("t") && head.len() > 1 && head.ends_with(['n', 'N']) {
+            let base = &head[..head.len() - 1];
+
+            return [
+                Some(Piece {
+                    range: start..apostrophe - 1,
+                    expansion: NEGATIONS
+                        .iter()
+                        .find(|(contracted, _)| contracted.eq_ignore_ascii_case(base))
+                        .map(|(_, expansion)| *expansion),
+                }),
+                Some(Piece {
+                    range: apostrophe - 1..end,
+                    expansion: Some("not"),
+                }),
+            ]
+            .into_iter()
+            .flatten();
+        } else if CONTRACTIONS
+            .iter()
+            .any(|contracted| contracted.eq_ignore_ascii_case(tail))
+        {
+            end = apostrophe;
+        }
+    }
+
+    let piece = (start < end).then_some(Piece {
+        range: start..end,
+        expansion: None,
+    });
+    [piece, None].into_iter().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pieces<'a>(word: &'a str, lang: &str) -> Vec<&'a str> {
+        split(word, lang)
+            .map(|piece| piece.expansion.unwrap_or(&word[piece.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(pieces("L'IVG", "fr"), vec!["IVG"]);
+        assert_eq!(pieces("(l'IVG)", "fr"), vec!["IVG)"]);
+        assert_eq!(pieces("qu’elle", ""), vec!["elle"]);
+        assert_eq!(pieces("dell'arte", "it"), vec!["arte"]);
+        assert_eq!(pieces("don't", "en"), vec!["do", "not"]);
+        assert_eq!(pieces("don't!", "en"), vec!["do", "not"]);
+        assert_eq!(pieces("Can’t", "en"), vec!["can", "not"]);
+        assert_eq!(pieces("Mbappé's", ""), vec!["Mbappé"]);
+        assert_eq!(pieces("they're", "en"), vec!["they"]);
+        assert_eq!(pieces("l'", "fr"), vec!["l"]);
+        assert_eq!(pieces("rock'n'roll", "en"), vec!["rock'n'roll"]);
+        assert_eq!(pieces("l'IVG", "en"), vec!["l'IVG"]);
+    }
+}
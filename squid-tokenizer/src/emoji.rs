@@ -0,0 +1,232 @@
+//! extraction of emoji.
+//!
+//! Emoji are often glued to words ("🚨De nombreux artistes"), so texts are
+//! split into extended grapheme clusters: sequences joined with a zero width
+//! joiner (👩‍🚀), skin tones (👍🏽), flags (🇫🇷) and keycaps (1️⃣) stay whole.
+
+use std::{
+    borrow::Cow,
+    iter,
+    ops::{Range, RangeInclusive},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Emoji and text presentation selectors.
+const VARIATION_SELECTORS: [char; 2] = ['\u{FE0E}', '\u{FE0F}'];
+/// Combining enclosing keycap, as in 1️⃣.
+const KEYCAP: char = '\u{20E3}';
+/// Emoji presentation selector.
+const EMOJI_SELECTOR: char = '\u{FE0F}';
+/// Zero width joiner, between the emoji of a sequence such as 👩‍🚀.
+const ZWJ: char = '\u{200D}';
+/// Skin tone modifiers, as in 👍🏽.
+const SKIN_TONES: RangeInclusive<char> = '\u{1F3FB}'..='\u{1F3FF}';
+
+/// Pictographs before U+1F000 displayed as emoji by default, from the
+/// `Emoji_Presentation` property. Others, such as "©" or "™", are text
+/// unless followed by [`EMOJI_SELECTOR`].
+const EMOJI_PRESENTATION: [(u32, u32); 33] = [
+    (0x231A, 0x231B),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+];
+
+/// Whether a grapheme cluster is an emoji.
+///
+/// Pictographs displayed as text by default, such as "☺" or "™", are only
+/// emoji when followed by the emoji presentation selector, a skin tone or
+/// another emoji of a sequence.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::emoji::is_emoji;
+///
+/// assert!(is_emoji("👩‍🚀"));
+/// assert!(is_emoji("☺️"));
+/// assert!(!is_emoji("☺"));
+/// assert!(!is_emoji("a"));
+/// ```
+pub fn is_emoji(grapheme: &str) -> bool {
+    let mut chars = grapheme.chars();
+
+    match chars.next() {
+        Some('0'..='9' | '#' | '*') => chars.any(|c| c == KEYCAP),
+        Some(c) if is_pictographic(c) => {
+            is_presentation(c)
+                || chars.as_str().starts_with(EMOJI_SELECTOR)
+                || chars.as_str().starts_with(|c| SKIN_TONES.contains(&c))
+                || chars.as_str().contains(ZWJ)
+        }
+        _ => false,
+    }
+}
+
+/// Returns the key of an emoji, without variation selectors, so "⚽️" and
+/// "⚽" are counted together.
+///
+/// Selectors are kept if the emoji is text without them, as "☺️".
+pub fn key(emoji: &str) -> Cow<'_, str> {
+    if !emoji.contains(VARIATION_SELECTORS) {
+        return Cow::Borrowed(emoji);
+    }
+
+    let key: String = emoji
+        .chars()
+        .filter(|c| !VARIATION_SELECTORS.contains(c))
+        .collect();
+    if is_emoji(&key) {
+        Cow::Owned(key)
+    } else {
+        Cow::Borrowed(emoji)
+    }
+}
+
+/// Splits a text into byte ranges of emoji and of the text between them,
+/// lazily.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::emoji::split;
+///
+/// let text = "🚨De";
+/// let pieces: Vec<&str> = split(text).map(|range| &text[range]).collect();
+///
+/// assert_eq!(pieces, vec!["🚨", "De"]);
+/// ```
+pub fn split(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    // ASCII texts have no emoji, and are not segmented into graphemes.
+    let graphemes = if text.is_ascii() { "" } else { text };
+    let mut emoji = graphemes
+        .grapheme_indices(true)
+        .filter(|(_, grapheme)| is_emoji(grapheme))
+        .map(|(index, grapheme)| index..index + grapheme.len());
+    let mut start = 0;
+    let mut next: Option<Range<usize>> = None;
+
+    iter::from_fn(move || {
+        if let Some(range) = next.take() {
+            return Some(range);
+        }
+
+        match emoji.next() {
+            Some(range) if start < range.start => {
+                let before = start..range.start;
+                start = range.end;
+                next = Some(range);
+                Some(before)
+            }
+            Some(range) => {
+                start = range.end;
+                Some(range)
+            }
+            None => {
+                let rest = start..text.len();
+                start = text.len();
+                (!rest.is_empty()).then_some(rest)
+            }
+        }
+    })
+}
+
+/// Whether a character is a pictograph which may be displayed as an emoji,
+/// from the `Extended_Pictographic` property and regional indicators.
+fn is_pictographic(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x00A9
+            | 0x00AE
+            | 0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x2194..=0x2199
+            | 0x21A9..=0x21AA
+            | 0x231A..=0x231B
+            | 0x2328
+            | 0x23CF
+            | 0x23E9..=0x23F3
+            | 0x23F8..=0x23FA
+            | 0x24C2
+            | 0x25AA..=0x25AB
+            | 0x25B6
+            | 0x25C0
+            | 0x25FB..=0x25FE
+            | 0x2600..=0x27BF
+            | 0x2934..=0x2935
+            | 0x2B05..=0x2B07
+            | 0x2B1B..=0x2B1C
+            | 0x2B50
+            | 0x2B55
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x1F000..=0x1FAFF
+    )
+}
+
+/// Whether a pictograph is displayed as an emoji by default.
+fn is_presentation(c: char) -> bool {
+    let c = c as u32;
+
+    c >= 0x1F000
+        || EMOJI_PRESENTATION
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let text = "Bravo🇫🇷👏🏽!1️⃣👨‍👩‍👧";
+        let pieces: Vec<&str> = split(text).map(|range| &text[range]).collect();
+
+        assert_eq!(pieces, vec!["Bravo", "🇫🇷", "👏🏽", "!", "1️⃣", "👨‍👩‍👧"]);
+        assert_eq!(key("⚽️"), "⚽");
+        assert_eq!(key("❤️"), "❤️");
+    }
+
+    #[test]
+    fn test_text_presentation() {
+        for text in ["Gravitalia™", "©2024", "Ⓜ ®"] {
+            assert_eq!(split(text).count(), 1, "{}", text);
+        }
+
+        assert!(is_emoji("™️") && is_emoji("☝🏽") && is_emoji("❤‍🔥"));
+        assert!(is_emoji("⚽") && is_emoji("✅"));
+        assert!(!is_emoji("❤") && !is_emoji("↔"));
+    }
+}
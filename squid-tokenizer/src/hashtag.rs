@@ -0,0 +1,246 @@
+//! segmentation of hashtags into words.
+//!
+//! Camel-case hashtags such as `#JourneeDesDroitsDesFemmes` are split on case
+//! changes. Lowercase ones such as `#soutienayanakamura` are broken using a
+//! frequency [`Dictionary`], which is either loaded from a file or learned
+//! from counted words.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{self, BufRead, BufReader},
+    ops::Range,
+    path::Path,
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
+/// Maximum number of distinct words learned by a [`Dictionary`].
+const MAX_WORDS: usize = 1_000_000;
+/// Maximum number of characters of a word learned by a [`Dictionary`].
+/// Splitting costs grow with the longest known word.
+const MAX_WORD_LENGTH: usize = 32;
+/// Frequency given to built-in stop words, which are never counted.
+const STOPWORD_FREQUENCY: u64 = 1_000;
+
+static DICTIONARY: OnceLock<RwLock<Dictionary>> = OnceLock::new();
+
+/// Frequency of words, used to find the most likely split of a hashtag.
+#[derive(Debug, Default, Clone)]
+pub struct Dictionary {
+    frequencies: HashMap<String, u64>,
+    total: u64,
+    max_length: usize,
+}
+
+impl Dictionary {
+    /// Reads a frequency list, with one word per line.
+    ///
+    /// Lines are either `word<whitespace>count` or a single word. In the
+    /// latter case, words are expected to be sorted from the most to the
+    /// least frequent and their frequency follows Zipf's law.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let reader = BufReader::new(OpenOptions::new().read(true).open(path)?);
+        let mut length = 0;
+
+        for (rank, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut columns = line.split_whitespace();
+
+            if let Some(word) = columns.next() {
+                let count = columns
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(1_000_000 / (rank as u64 + 1));
+                self.add(&word.to_lowercase(), count.max(1));
+                length += 1;
+            }
+        }
+
+        Ok(length)
+    }
+
+    /// Increments the frequency of a word.
+    ///
+    /// Words of one character or of more than 32 characters are ignored.
+    /// Once a million words are known, only their frequency changes.
+    pub fn learn(&mut self, word: &str) {
+        self.add(word, 1);
+    }
+
+    fn add(&mut self, word: &str, count: u64) {
+        let length = word.chars().count();
+        if !(2..=MAX_WORD_LENGTH).contains(&length) || !word.chars().all(char::is_alphanumeric) {
+            return;
+        }
+
+        if let Some(frequency) = self.frequencies.get_mut(word) {
+            *frequency += count;
+        } else if self.frequencies.len() < MAX_WORDS {
+            self.frequencies.insert(word.to_string(), count);
+            self.max_length = self.max_length.max(length);
+        } else {
+            return;
+        }
+
+        self.total += count;
+    }
+
+    /// Splits a word into dictionary words, regardless of its case.
+    ///
+    /// Returns byte ranges of the words. Returns the whole word if it cannot
+    /// be entirely split into known words.
+    pub fn split(&self, word: &str) -> Vec<Range<usize>> {
+        let chars: Vec<(usize, String)> = word
+            .char_indices()
+            .map(|(index, c)| (index, c.to_lowercase().collect()))
+            .collect();
+        let offset = |position: usize| chars.get(position).map_or(word.len(), |(i, _)| *i);
+
+        // best[i] = (cost, start) of the cheapest split of the first i chars.
+        let mut best: Vec<(f64, usize)> = vec![(f64::INFINITY, 0); chars.len() + 1];
+        best[0] = (0.0, 0);
+        let max_length = self.max_length.min(MAX_WORD_LENGTH);
+        let mut candidate = String::with_capacity(max_length * 4);
+
+        for end in 1..=chars.len() {
+            for start in end.saturating_sub(max_length)..end {
+                if best[start].0.is_infinite() {
+                    continue;
+                }
+
+                candidate.clear();
+                candidate.extend(chars[start..end].iter().map(|(_, c)| c.as_str()));
+                if let Some(frequency) = self.frequencies.get(&candidate) {
+                    let cost = best[start].0 + (self.total as f64 / *frequency as f64).ln();
+                    if cost < best[end].0 {
+                        best[end] = (cost, start);
+                    }
+                }
+            }
+        }
+
+        if best[chars.len()].0.is_infinite() {
+            #[allow(clippy::single_range_in_vec_init)]
+            return vec![0..word.len()];
+        }
+
+        let mut ranges = Vec::new();
+        let mut end = chars.len();
+        while end > 0 {
+            let start = best[end].1;
+            ranges.push(offset(start)..offset(end));
+            end = start;
+        }
+        ranges.reverse();
+
+        ranges
+    }
+}
+
+/// Returns the global [`Dictionary`], seeded with built-in stop words.
+pub fn dictionary() -> &'static RwLock<Dictionary> {
+    DICTIONARY.get_or_init(|| {
+        let mut dictionary = Dictionary::default();
+
+        for lang in ["de", "en", "es", "fr", "it"] {
+            for word in crate::stopwords::builtin(lang).into_iter().flatten() {
+                dictionary.add(word, STOPWORD_FREQUENCY);
+            }
+        }
+
+        RwLock::new(dictionary)
+    })
+}
+
+/// Splits the body of a hashtag, without `#`, into words.
+///
+/// Camel-case hashtags are split on case changes and between letters and
+/// digits. Other ones are split using the global [`dictionary`]. Returns
+/// slices of the hashtag.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::hashtag::segment;
+///
+/// assert_eq!(
+///     segment("JourneeDesDroitsDesFemmes"),
+///     vec!["Journee", "Des", "Droits", "Des", "Femmes"]
+/// );
+/// assert_eq!(segment("8mars"), vec!["8", "mars"]);
+/// ```
+pub fn segment(hashtag: &str) -> Vec<&str> {
+    ranges(hashtag)
+        .into_iter()
+        .map(|range| &hashtag[range])
+        .collect()
+}
+
+/// Same as [`segment`], but returns byte ranges of the words.
+pub(crate) fn ranges(hashtag: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+
+    for (index, c) in hashtag.char_indices() {
+        if let Some(previous) = previous {
+            let boundary = (previous.is_lowercase() && c.is_uppercase())
+                || (previous.is_alphabetic() && c.is_numeric())
+                || (previous.is_numeric() && c.is_alphabetic());
+
+            if boundary {
+                ranges.push(start..index);
+                start = index;
+            }
+        }
+        previous = Some(c);
+    }
+    ranges.push(start..hashtag.len());
+
+    if ranges.len() == 1 {
+        ranges = dictionary()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .split(hashtag);
+    }
+
+    ranges.retain(|range| !range.is_empty());
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let mut dictionary = Dictionary::default();
+        for word in [
+            "soutien", "soutien", "aya", "nakamura", "naka", "mura", "sou",
+        ] {
+            dictionary.learn(word);
+        }
+
+        let word = "soutienayanakamura";
+        let words: Vec<&str> = dictionary
+            .split(word)
+            .into_iter()
+            .map(|range| &word[range])
+            .collect();
+
+        assert_eq!(words, vec!["soutien", "aya", "nakamura"]);
+        assert_eq!(dictionary.split("inconnu"), vec![0..7]);
+
+        dictionary.learn(&"a".repeat(MAX_WORD_LENGTH + 1));
+        assert_eq!(dictionary.max_length, "nakamura".len());
+    }
+
+    #[test]
+    fn test_segment() {
+        assert_eq!(
+            segment("SoutienAyaNakamura"),
+            vec!["Soutien", "Aya", "Nakamura"]
+        );
+        assert_eq!(segment("IVG"), vec!["IVG"]);
+        assert_eq!(segment("JO2024"), vec!["JO", "2024"]);
+    }
+}
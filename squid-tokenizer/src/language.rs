@@ -0,0 +1,185 @@
+//! automatic language identification.
+//!
+//! Texts are compared with character trigram profiles embedded for 69
+//! languages, after their script has been identified.
+
+use whatlang::{Detector, Lang};
+
+/// Result of a language identification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    /// ISO 639-1 code of the language (e.g. `fr`).
+    pub lang: &'static str,
+    /// Confidence between 0 and 1.
+    pub confidence: f64,
+}
+
+/// Identifies the language of a text.
+///
+/// Returns [`None`] if the text does not contain enough letters.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::language::detect;
+///
+/// let detection = detect("Retour en images sur la table ronde organisée ce matin").unwrap();
+/// assert_eq!(detection.lang, "fr");
+/// ```
+pub fn detect(text: &str) -> Option<Detection> {
+    Detector::new().detect(text).map(|info| Detection {
+        lang: code(info.lang()),
+        confidence: info.confidence(),
+    })
+}
+
+/// Identifies the language of a text among the given ISO 639-1 codes.
+///
+/// Unknown codes are ignored. Returns [`None`] if none of them is known.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::language::detect_among;
+///
+/// let text = "The fans reaction after Mbappé got subbed on";
+/// let detection = detect_among(text, &["fr", "en"]);
+/// assert_eq!(detection.unwrap().lang, "en");
+/// ```
+pub fn detect_among(text: &str, langs: &[&str]) -> Option<Detection> {
+    let allowlist: Vec<Lang> = Lang::all()
+        .iter()
+        .filter(|lang| langs.contains(&code(**lang)))
+        .copied()
+        .collect();
+
+    if allowlist.is_empty() {
+        return None;
+    }
+
+    Detector::with_allowlist(allowlist)
+        .detect(text)
+        .map(|info| Detection {
+            lang: code(info.lang()),
+            confidence: info.confidence(),
+        })
+}
+
+/// Whether a language is written as an ISO 639-1 code, made of two
+/// lowercase letters.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::language::is_code;
+///
+/// assert!(is_code("fr"));
+/// assert!(!is_code("FR"));
+/// assert!(!is_code("fra"));
+/// ```
+pub fn is_code(lang: &str) -> bool {
+    lang.len() == 2 && lang.bytes().all(|byte| byte.is_ascii_lowercase())
+}
+
+/// Returns the ISO 639-1 code of a language, as used by [`crate::stem`] and
+/// [`crate::stopwords`].
+fn code(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Afr => "af",
+        Lang::Aka => "ak",
+        Lang::Amh => "am",
+        Lang::Ara => "ar",
+        Lang::Aze => "az",
+        Lang::Bel => "be",
+        Lang::Ben => "bn",
+        Lang::Bul => "bg",
+        Lang::Cat => "ca",
+        Lang::Ces => "cs",
+        Lang::Cmn => "zh",
+        Lang::Dan => "da",
+        Lang::Deu => "de",
+        Lang::Ell => "el",
+        Lang::Eng => "en",
+        Lang::Epo => "eo",
+        Lang::Est => "et",
+        Lang::Fin => "fi",
+        Lang::Fra => "fr",
+        Lang::Guj => "gu",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Hrv => "hr",
+        Lang::Hun => "hu",
+        Lang::Hye => "hy",
+        Lang::Ind => "id",
+        Lang::Ita => "it",
+        Lang::Jav => "jv",
+        Lang::Jpn => "ja",
+        Lang::Kan => "kn",
+        Lang::Kat => "ka",
+        Lang::Khm => "km",
+        Lang::Kor => "ko",
+        Lang::Lat => "la",
+        Lang::Lav => "lv",
+        Lang::Lit => "lt",
+        Lang::Mal => "ml",
+        Lang::Mar => "mr",
+        Lang::Mkd => "mk",
+        Lang::Mya => "my",
+        Lang::Nep => "ne",
+        Lang::Nld => "nl",
+        Lang::Nob => "nb",
+        Lang::Ori => "or",
+        Lang::Pan => "pa",
+        Lang::Pes => "fa",
+        Lang::Pol => "pl",
+        Lang::Por => "pt",
+        Lang::Ron => "ro",
+        Lang::Rus => "ru",
+        Lang::Sin => "si",
+        Lang::Slk => "sk",
+        Lang::Slv => "sl",
+        Lang::Sna => "sn",
+        Lang::Spa => "es",
+        Lang::Srp => "sr",
+        Lang::Swe => "sv",
+        Lang::Tam => "ta",
+        Lang::Tel => "te",
+        Lang::Tgl => "tl",
+        Lang::Tha => "th",
+        Lang::Tuk => "tk",
+        Lang::Tur => "tr",
+        Lang::Ukr => "uk",
+        Lang::Urd => "ur",
+        Lang::Uzb => "uz",
+        Lang::Vie => "vi",
+        Lang::Yid => "yi",
+        Lang::Zul => "zu",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let samples = [
+            (
+                "À nos grands-mères, à nos mères, à nos femmes, à nos filles",
+                "fr",
+            ),
+            ("The fans reaction after Mbappé got subbed on", "en"),
+            ("El combate continúa en todo el país por los derechos", "es"),
+            (
+                "Die Kinder spielen heute im Garten mit ihren Freunden",
+                "de",
+            ),
+            (
+                "Американский рэпер находится в топе твитов во Франции",
+                "ru",
+            ),
+        ];
+
+        for (text, lang) in samples {
+            assert_eq!(detect(text).unwrap().lang, lang, "{}", text);
+        }
+        assert!(detect("").is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! dictionary-based lemmatization of words.
+//!
+//! Lemma tables map inflected forms to their lemma, so "allons", "vont" and
+//! "irai" all become "aller". Tables are tab-separated files, one form per
+//! line, such as Lefff or Morphalou exports. Lines starting with `#` are
+//! ignored.
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+static LEMMATIZER: OnceLock<Lemmatizer> = OnceLock::new();
+
+/// Columns to read from a lemma table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableFormat {
+    /// Index of the column containing the inflected form.
+    pub form: usize,
+    /// Index of the column containing the lemma.
+    pub lemma: usize,
+}
+
+impl TableFormat {
+    /// `form<TAB>category<TAB>lemma<TAB>...`, used by Lefff.
+    pub const LEFFF: TableFormat = TableFormat { form: 0, lemma: 2 };
+}
+
+impl Default for TableFormat {
+    /// `form<TAB>lemma`.
+    fn default() -> Self {
+        TableFormat { form: 0, lemma: 1 }
+    }
+}
+
+/// Lemma tables, by language.
+///
+/// # Example
+/// ```no_run,rust
+/// use squid_tokenizer::lemma::{lemmatizer, TableFormat};
+///
+/// lemmatizer()
+///     .load("fr", "./lemmas/fr.tsv", TableFormat::default())
+///     .unwrap();
+///
+/// assert_eq!(lemmatizer().lemmatize("allons", "fr"), "aller");
+/// ```
+#[derive(Debug, Default)]
+pub struct Lemmatizer {
+    tables: RwLock<HashMap<String, Arc<Table>>>,
+}
+
+/// Lemma table of a language.
+#[derive(Debug, Default, Clone)]
+struct Table {
+    /// Lemma of each inflected form.
+    forms: HashMap<String, String>,
+    /// Lemmas of the table, which are known words even without a row mapping
+    /// them to themselves.
+    lemmas: HashSet<String>,
+}
+
+impl Table {
+    fn insert(&mut self, form: String, lemma: String) {
+        if form != lemma {
+            // Keep the first lemma of ambiguous forms.
+            self.forms.entry(form).or_insert_with(|| lemma.clone());
+        }
+        self.lemmas.insert(lemma);
+    }
+}
+
+impl Lemmatizer {
+    /// Reads a lemma table and uses it for a language, identified by its
+    /// ISO 639-1 code (e.g. `fr`).
+    ///
+    /// Replaces the previous table of the language. Returns the number of
+    /// forms loaded.
+    pub fn load<P: AsRef<Path>>(
+        &self,
+        lang: &str,
+        path: P,
+        format: TableFormat,
+    ) -> io::Result<usize> {
+        let mut table = Table::default();
+
+        for line in BufReader::new(OpenOptions::new().read(true).open(path)?).lines() {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('\t').collect();
+            if let (Some(form), Some(lemma)) = (columns.get(format.form), columns.get(format.lemma))
+            {
+                let (form, lemma) = (form.trim().to_lowercase(), lemma.trim().to_lowercase());
+                if !form.is_empty() && !lemma.is_empty() {
+                    table.insert(form, lemma);
+                }
+            }
+        }
+
+        let length = table.forms.len();
+        self.tables
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(lang.to_string(), Arc::new(table));
+
+        Ok(length)
+    }
+
+    /// Adds forms to the table of a language.
+    ///
+    /// Forms and lemmas are lowercased, as when loading a table.
+    pub fn extend<I, F, L>(&self, lang: &str, forms: I)
+    where
+        I: IntoIterator<Item = (F, L)>,
+        F: ToString,
+        L: ToString,
+    {
+        let mut tables = self.tables.write().unwrap_or_else(PoisonError::into_inner);
+        let table = tables.entry(lang.to_string()).or_default();
+
+        let table = Arc::make_mut(table);
+
+        for (form, lemma) in forms {
+            let (form, lemma) = (
+                form.to_string().trim().to_lowercase(),
+                lemma.to_string().trim().to_lowercase(),
+            );
+            if !form.is_empty() && !lemma.is_empty() {
+                table.insert(form, lemma);
+            }
+        }
+    }
+
+    /// Whether a table is loaded for a language.
+    pub fn has_table(&self, lang: &str) -> bool {
+        self.tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(lang)
+    }
+
+    /// Whether a lowercase word is an inflected form or a lemma of the table
+    /// of a language.
+    pub fn contains(&self, word: &str, lang: &str) -> bool {
+        self.tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(lang)
+            .is_some_and(|table| table.forms.contains_key(word) || table.lemmas.contains(word))
+    }
+
+    /// Returns the lemma of a lowercase word.
+    ///
+    /// Unknown words are returned unchanged.
+    pub fn lemmatize<'a>(&self, word: &'a str, lang: &str) -> Cow<'a, str> {
+        match self
+            .tables
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(lang)
+            .and_then(|table| table.forms.get(word))
+        {
+            Some(lemma) => Cow::Owned(lemma.clone()),
+            None => Cow::Borrowed(word),
+        }
+    }
+}
+
+/// Returns the global [`Lemmatizer`].
+pub fn lemmatizer() -> &'static Lemmatizer {
+    LEMMATIZER.get_or_init(Lemmatizer::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join("squid_test_lemma_load.tsv");
+        std::fs::write(
+            &path,
+            "# form\tcategory\tlemma\nallons\tv\taller\nvont\tv\taller\nIrai\tv\tAller\n",
+        )
+        .unwrap();
+
+        let lemmatizer = Lemmatizer::default();
+        assert_eq!(lemmatizer.load("fr", &path, TableFormat::LEFFF).unwrap(), 3);
+        std::fs::remove_file(&path).unwrap();
+
+        for form in ["allons", "vont", "irai"] {
+            assert_eq!(lemmatizer.lemmatize(form, "fr"), "aller");
+        }
+        assert_eq!(lemmatizer.lemmatize("allons", "en"), "allons");
+        assert_eq!(lemmatizer.lemmatize("marchons", "fr"), "marchons");
+        assert!(lemmatizer.contains("vont", "fr") && lemmatizer.contains("aller", "fr"));
+        assert!(!lemmatizer.contains("marchons", "fr"));
+    }
+
+    #[test]
+    fn test_extend() {
+        let lemmatizer = Lemmatizer::default();
+        lemmatizer.extend("fr", [("Allons", "ALLER"), (" vont ", "aller"), ("", "x")]);
+
+        assert!(lemmatizer.has_table("fr"));
+        assert_eq!(lemmatizer.lemmatize("allons", "fr"), "aller");
+        assert_eq!(lemmatizer.lemmatize("vont", "fr"), "aller");
+        assert_eq!(lemmatizer.lemmatize("", "fr"), "");
+    }
+}
@@ -1,41 +1,747 @@
+pub mod config;
+pub mod elision;
+pub mod emoji;
+pub mod hashtag;
+pub mod language;
+pub mod lemma;
+pub mod link;
+pub mod normalize;
+pub mod number;
+pub mod phrase;
+pub mod profanity;
+pub mod slang;
+pub mod stem;
 pub mod stopwords;
+pub mod transliterate;
+pub mod words;
 
-use std::{collections::HashSet, convert::Infallible, path::Path};
-
-/// Lowercase words, remove punctuation, separate words into tokens and convert them into numbers.
-pub fn tokenize<T: ToString>(text: T) -> Result<String, Infallible> {
-    stopwords::init(Path::new("./stopwords").to_path_buf());
-
-    let punctuation: HashSet<char> = ['!', ',', '.', ':', ';', '?', '-', '\"', '(', ')']
-        .iter()
-        .cloned()
-        .collect();
-
-    let result_string: String = stopwords::remove_words_from_sentence(
-        text.to_string()
-            .replace('\'', " ")
-            .to_lowercase()
-            .chars()
-            .filter(|c| !punctuation.contains(c))
-            .collect::<String>()
-            .split_ascii_whitespace()
-            .filter(|c| *c != " " && c.len() > 1)
-            .map(|c| format!("{} ", c))
-            .collect(),
-    );
-
-    let normalize = result_string
-        .chars()
-        .map(|c| {
-            if c.len_utf8() > 1 {
-                c.escape_unicode().to_string()
-            } else {
-                c.to_string()
+use config::{Apostrophe, TokenizerConfig};
+use link::Policy;
+use phrase::Phrases;
+use squid_error::{Error, ErrorType, TokenizerError};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    ops::Range,
+    sync::{Arc, OnceLock},
+};
+use stopwords::StopWords;
+use transliterate::Scheme;
+
+/// What a [`Token`] represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// Any other word.
+    Word,
+    /// Word starting with `#`.
+    Hashtag,
+    /// User name starting with `@`.
+    Mention,
+    /// Link starting with `http://`, `https://` or `www.`, or its domain
+    /// name.
+    Url,
+    /// E-mail address, or its domain name.
+    Email,
+    /// Pictogram such as 🚨, including sequences such as 👩‍🚀 or 🇫🇷.
+    Emoji,
+    /// Number, ordinal or quantity, such as "2024", "1er" or "10k". See
+    /// [`number`].
+    Number,
+    /// Day of a month, such as "8mars", or date written with digits, such as
+    /// "08/03/2024".
+    Date,
+    /// Proper-noun phrase, such as "Aya Nakamura". See [`phrase`].
+    Phrase,
+}
+
+impl TokenKind {
+    /// Guesses the kind of an already processed token.
+    ///
+    /// # Example
+    /// ```rust
+    /// use squid_tokenizer::TokenKind;
+    ///
+    /// assert_eq!(TokenKind::of("#ivg"), TokenKind::Hashtag);
+    /// assert_eq!(TokenKind::of("2024"), TokenKind::Number);
+    /// assert_eq!(TokenKind::of("8 mars"), TokenKind::Date);
+    /// assert_eq!(TokenKind::of("lemonde.fr"), TokenKind::Url);
+    /// assert_eq!(TokenKind::of("aya nakamura"), TokenKind::Phrase);
+    /// ```
+    pub fn of(text: &str) -> Self {
+        if emoji::is_emoji(text) {
+            TokenKind::Emoji
+        } else if text.starts_with('#') {
+            TokenKind::Hashtag
+        } else if text.starts_with('@') {
+            TokenKind::Mention
+        } else if text
+            .split_once('@')
+            .is_some_and(|(_, domain)| domain.contains('.'))
+        {
+            TokenKind::Email
+        } else if let Some(kind) = number::kind(text) {
+            kind
+        } else if text.contains(' ') {
+            TokenKind::Phrase
+        } else if text.contains('.') || text.contains("://") {
+            // Punctuation is removed from words, not from links.
+            TokenKind::Url
+        } else {
+            TokenKind::Word
+        }
+    }
+}
+
+/// A word extracted from a text.
+///
+/// Both texts borrow from the original text when they can, so most tokens
+/// are read without allocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// Processed text, used as key for ranking.
+    pub text: Cow<'a, str>,
+    /// Text as written, without surrounding punctuation, to show instead of
+    /// the key. Contractions show their full form, such as "not" for "n't".
+    pub display: Cow<'a, str>,
+    /// What the token represents.
+    pub kind: TokenKind,
+    /// Position of the token in the original text.
+    pub byte_range: Range<usize>,
+    /// Whether the token is an abusive term of the
+    /// [`profanity::lexicon`].
+    pub flagged: bool,
+}
+
+/// Iterator over the [`Token`]s of a text.
+///
+/// Created by [`tokens`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    text: &'a str,
+    position: usize,
+    service: &'a str,
+    lang: &'a str,
+    config: Cow<'a, TokenizerConfig>,
+    stopwords: Option<Arc<StopWords>>,
+    phrases: Phrases<'a>,
+    pending: VecDeque<Token<'a>>,
+}
+
+impl<'a> Tokens<'a> {
+    /// Replaces every setting of the pipeline.
+    pub fn config(mut self, config: &'a TokenizerConfig) -> Self {
+        self.config = Cow::Borrowed(config);
+        self
+    }
+
+    /// Sets the service the text belongs to.
+    ///
+    /// It selects the custom stop words to remove from the
+    /// [`stopwords::registry`].
+    pub fn service(mut self, service: &'a str) -> Self {
+        self.service = service;
+        self.stopwords = None;
+        self
+    }
+
+    /// Sets the language of the text, as an ISO 639-1 code (e.g. `fr`).
+    ///
+    /// It selects the built-in stop words to remove.
+    pub fn lang(mut self, lang: &'a str) -> Self {
+        self.lang = lang;
+        self.stopwords = None;
+        self
+    }
+
+    /// Collapses runs of three or more identical letters, so "loooool" and
+    /// "looool" both become "lol".
+    ///
+    /// Runs become one letter when the word is then a stop word or a form of
+    /// the lemma table of the language, such as "so" for "soooo", and two
+    /// letters otherwise. Only [`TokenKind::Word`] and [`TokenKind::Hashtag`]
+    /// tokens are collapsed, not links or numbers. See
+    /// [`slang::collapse_repeats_with`].
+    pub fn collapse_repeats(mut self, enabled: bool) -> Self {
+        self.config.to_mut().collapse_repeats = enabled;
+        self
+    }
+
+    /// Replaces digits and symbols used as letters, so "m4cron" becomes
+    /// "macron" and "gr8" becomes "great".
+    ///
+    /// Only [`TokenKind::Word`] and [`TokenKind::Hashtag`] tokens are
+    /// folded. See [`slang::fold_leet`].
+    pub fn fold_leet(mut self, enabled: bool) -> Self {
+        self.config.to_mut().fold_leet = enabled;
+        self
+    }
+
+    /// Flags tokens matching the [`profanity::lexicon`] of the
+    /// [language](Tokens::lang), or of any language if it is unknown.
+    ///
+    /// See [`Token::flagged`].
+    pub fn flag_profanity(mut self, enabled: bool) -> Self {
+        self.config.to_mut().flag_profanity = enabled;
+        self
+    }
+
+    /// Removes diacritics from tokens, so "journée" and "journee" share the
+    /// same key.
+    ///
+    /// The original spelling stays available as [`Token::display`].
+    pub fn fold_accents(mut self, enabled: bool) -> Self {
+        self.config.to_mut().fold_accents = enabled;
+        self
+    }
+
+    /// Transliterates Cyrillic and Greek tokens to Latin script with a
+    /// scheme, so "Зеленский" and "zelenskiy" share the same key. Disabled
+    /// with [`None`].
+    ///
+    /// The original spelling stays available as [`Token::display`]. See
+    /// [`transliterate::to_latin`].
+    pub fn transliterate(mut self, scheme: Option<Scheme>) -> Self {
+        self.config.to_mut().transliterate = scheme;
+        self
+    }
+
+    /// Replaces words by their lemma, using the table of the
+    /// [language](Tokens::lang) loaded in the [`lemma::lemmatizer`], so
+    /// "allons" and "vont" both become "aller".
+    ///
+    /// Only [`TokenKind::Word`] tokens are lemmatized, before stemming.
+    pub fn lemmatize(mut self, enabled: bool) -> Self {
+        self.config.to_mut().lemmatize = enabled;
+        self
+    }
+
+    /// Reduces words to their stem using the Snowball stemmer of the
+    /// [language](Tokens::lang), so "manifestation" and "manifestations"
+    /// share the same key.
+    ///
+    /// Only [`TokenKind::Word`] tokens are stemmed. The original spelling
+    /// stays available as [`Token::display`].
+    pub fn stem(mut self, enabled: bool) -> Self {
+        self.config.to_mut().stem = enabled;
+        self
+    }
+
+    /// Also emits the words of hashtags, after the hashtag itself, so
+    /// "#JourneeDesDroitsDesFemmes" counts for "journee", "droits" and
+    /// "femmes" too.
+    ///
+    /// See [`hashtag::segment`].
+    pub fn segment_hashtags(mut self, enabled: bool) -> Self {
+        self.config.to_mut().segment_hashtags = enabled;
+        self
+    }
+
+    /// Also emits proper-noun phrases, such as "Aya Nakamura", after their
+    /// last word. Their key is made of the keys of their words, separated by
+    /// a space, and they are flagged if one of their words is.
+    ///
+    /// See [`phrase::Phrases`].
+    pub fn phrases(mut self, enabled: bool) -> Self {
+        self.config.to_mut().phrases = enabled;
+        self
+    }
+
+    /// Sets what to do with numbers and dates, such as "2024", "1er", "10k"
+    /// or "8mars". Normalizing them counts "8mars" and "8 mars" together.
+    ///
+    /// Hashtags, such as "#8mars", are always kept. See [`number::parse`].
+    pub fn numbers(mut self, policy: number::Policy) -> Self {
+        self.config.to_mut().numbers = policy;
+        self
+    }
+
+    /// Sets what to do with URLs. Keeping only their domain name allows
+    /// ranking the most linked websites.
+    pub fn urls(mut self, policy: Policy) -> Self {
+        self.config.to_mut().urls = policy;
+        self
+    }
+
+    /// Sets what to do with mentions, such as "@gravitalia".
+    pub fn mentions(mut self, policy: Policy) -> Self {
+        self.config.to_mut().mentions = policy;
+        self
+    }
+
+    /// Sets what to do with e-mail addresses.
+    pub fn emails(mut self, policy: Policy) -> Self {
+        self.config.to_mut().emails = policy;
+        self
+    }
+
+    /// Queues the tokens of a chunk of text without separators.
+    fn push(&mut self, range: Range<usize>, stopwords: &StopWords) {
+        // Neither emoji nor unspaced scripts.
+        if self.text[range.clone()].is_ascii() {
+            return self.push_word(range, stopwords);
+        }
+
+        let text = self.text;
+        for piece in emoji::split(&text[range.clone()]) {
+            let offset = range.start + piece.start;
+            let piece = &text[offset..range.start + piece.end];
+
+            if emoji::is_emoji(piece) {
+                let text = emoji::key(piece);
+                let token = Token {
+                    flagged: self.is_flagged(&text),
+                    text,
+                    display: Cow::Borrowed(piece),
+                    kind: TokenKind::Emoji,
+                    byte_range: offset..offset + piece.len(),
+                };
+                self.queue(token, stopwords);
+                continue;
+            }
+
+            if self.is_whole(piece) {
+                self.push_word(offset..offset + piece.len(), stopwords);
+                continue;
             }
+
+            for range in words::split(piece) {
+                self.push_word(offset + range.start..offset + range.end, stopwords);
+            }
+        }
+    }
+
+    /// Whether a piece of text is a link, an e-mail address, a hashtag or a
+    /// mention, not to split into the words of unspaced scripts.
+    fn is_whole(&self, piece: &str) -> bool {
+        let punctuation = &self.config.punctuation;
+        let trimmed = piece.trim_matches(|c| punctuation.contains(&c));
+
+        trimmed.starts_with(['#', '@'])
+            || link::url_domain(trimmed).is_some()
+            || link::email_domain(trimmed).is_some()
+    }
+
+    /// Queues the token of a word, followed by the words of hashtags.
+    fn push_word(&mut self, range: Range<usize>, stopwords: &StopWords) {
+        let text = self.text;
+        let Some(token) = self.process(&text[range.clone()], range.start, stopwords) else {
+            return;
+        };
+
+        let hashtag = (token.kind == TokenKind::Hashtag).then(|| token.byte_range.clone());
+        self.queue(token, stopwords);
+
+        if let Some(range) = hashtag.filter(|_| self.config.segment_hashtags) {
+            self.segment(range, stopwords);
+        }
+    }
+
+    /// Queues a token of the text, after the phrase it ends, if any.
+    fn queue(&mut self, token: Token<'a>, stopwords: &StopWords) {
+        if self.config.phrases {
+            if let Some(phrase) = self.phrases.push(self.text, &token, stopwords) {
+                self.push_phrase(phrase, stopwords);
+            }
+        }
+
+        self.pending.push_back(token);
+    }
+
+    /// Queues a phrase, unless it is a stop word.
+    fn push_phrase(&mut self, mut phrase: Token<'a>, stopwords: &StopWords) {
+        phrase.text = Cow::Owned(self.phrase_key(&phrase.text, stopwords));
+        if self.config.remove_stopwords && stopwords.contains(phrase.text.as_ref()) {
+            return;
+        }
+
+        phrase.flagged |= self.is_flagged(&phrase.text);
+        self.pending.push_back(phrase);
+    }
+
+    /// Whether a word is an abusive term, if they are flagged.
+    fn is_flagged(&self, word: &str) -> bool {
+        self.config.flag_profanity && profanity::lexicon().matches(word, self.lang)
+    }
+
+    /// Whether a character splits two tokens.
+    fn is_separator(&self, c: char) -> bool {
+        words::is_separator(c)
+            || (elision::is_apostrophe(c) && self.config.apostrophe == Apostrophe::Split)
+    }
+
+    /// Queues the words of the hashtag at a byte range of the text.
+    fn segment(&mut self, range: Range<usize>, stopwords: &StopWords) {
+        let text = self.text;
+        let hashtag = &text[range.clone()];
+        let body = hashtag.trim_start_matches('#');
+        let ranges = hashtag::ranges(body);
+
+        if ranges.len() < 2 {
+            return;
+        }
+
+        let offset = range.start + hashtag.len() - body.len();
+        for range in ranges {
+            if let Some(token) = self.process(&body[range.clone()], offset + range.start, stopwords)
+            {
+                self.pending.push_back(token);
+            }
+        }
+    }
+
+    /// Turns a raw chunk of text into a [`Token`], if it is worth keeping.
+    fn process(&self, raw: &'a str, offset: usize, stopwords: &StopWords) -> Option<Token<'a>> {
+        let punctuation = &self.config.punctuation;
+        let trimmed = raw.trim_start_matches(|c| punctuation.contains(&c));
+        let start = offset + raw.len() - trimmed.len();
+        let trimmed = trimmed.trim_end_matches(|c| punctuation.contains(&c));
+        let byte_range = start..start + trimmed.len();
+
+        let link = link::url_domain(trimmed)
+            .map(|domain| (domain, TokenKind::Url, self.config.urls))
+            .or_else(|| {
+                link::email_domain(trimmed)
+                    .map(|domain| (domain, TokenKind::Email, self.config.emails))
+            });
+        if let Some((domain, kind, policy)) = link {
+            let display = match policy {
+                Policy::Drop => return None,
+                Policy::Keep => trimmed,
+                Policy::Domain => domain,
+            };
+
+            return Some(Token {
+                text: normalize::normalize(display),
+                display: Cow::Borrowed(display),
+                kind,
+                byte_range,
+                flagged: false,
+            });
+        }
+
+        // Before punctuation is removed, so "1,5k" is not read as "15k".
+        if let Some(numeral) = number::parse(trimmed, self.lang) {
+            if trimmed.chars().count() < self.config.min_length {
+                return None;
+            }
+
+            let text = match self.config.numbers {
+                number::Policy::Drop => return None,
+                number::Policy::Keep => normalize::normalize(trimmed),
+                number::Policy::Normalize => Cow::Owned(numeral.normalized),
+            };
+
+            return Some(Token {
+                text,
+                display: Cow::Borrowed(trimmed),
+                kind: numeral.kind,
+                byte_range,
+                flagged: false,
+            });
+        }
+
+        let mut word = self.clean(trimmed);
+
+        let kind = TokenKind::of(&word);
+        if kind == TokenKind::Mention && self.config.mentions == Policy::Drop {
+            return None;
+        }
+
+        if matches!(kind, TokenKind::Word | TokenKind::Hashtag) {
+            self.spell(&mut word, stopwords);
+        }
+
+        // A single ideograph is a word.
+        let short = word.chars().count() < self.config.min_length
+            && (word.is_empty() || !word.chars().all(words::is_unspaced));
+        if short || (self.config.remove_stopwords && stopwords.contains(word.as_ref())) {
+            return None;
+        }
+
+        // As written too, since runs of letters are collapsed into two.
+        let flagged =
+            kind != TokenKind::Mention && (self.is_flagged(&word) || self.is_flagged(trimmed));
+
+        if self.config.lemmatize && kind == TokenKind::Word {
+            apply(&mut word, |word| {
+                lemma::lemmatizer().lemmatize(word, self.lang)
+            });
+        }
+
+        if self.config.stem && kind == TokenKind::Word {
+            apply(&mut word, |word| stem::stem(word, self.lang));
+        }
+
+        self.script(&mut word);
+
+        // Stop words added from counted keys are processed words.
+        if self.config.remove_stopwords && stopwords.contains(word.as_ref()) {
+            return None;
+        }
+
+        Some(Token {
+            text: word,
+            display: Cow::Borrowed(trimmed),
+            kind,
+            byte_range,
+            flagged,
         })
-        .collect::<String>();
+    }
+
+    /// Lowercases a word without surrounding punctuation, removing the
+    /// punctuation inside it.
+    fn clean<'t>(&self, trimmed: &'t str) -> Cow<'t, str> {
+        let punctuation = &self.config.punctuation;
+        let kept = |c: &char| {
+            !punctuation.contains(c)
+                && (!elision::is_apostrophe(*c) || self.config.apostrophe != Apostrophe::Remove)
+        };
+
+        // Words without uppercase letters nor punctuation inside are
+        // borrowed.
+        if trimmed.is_ascii() {
+            if trimmed.chars().all(|c| kept(&c) && !c.is_ascii_uppercase()) {
+                Cow::Borrowed(trimmed)
+            } else {
+                // Lowercased while filtered, without intermediate string.
+                let mut word = String::with_capacity(trimmed.len());
+                word.extend(trimmed.chars().filter(kept).map(|c| c.to_ascii_lowercase()));
+                Cow::Owned(word)
+            }
+        } else {
+            let word = normalize::normalize(trimmed);
+            if word.chars().all(|c| kept(&c) && c != '’') {
+                word
+            } else {
+                let mut word = word.into_owned();
+                word.retain(|c| kept(&c));
+                Cow::Owned(word.replace('’', "'"))
+            }
+        }
+    }
+
+    /// Rewrites elongated and leetspeak spellings of a word, if enabled.
+    fn spell(&self, word: &mut Cow<'_, str>, stopwords: &StopWords) {
+        if self.config.collapse_repeats {
+            apply(word, |word| {
+                slang::collapse_repeats_with(word, |collapsed| {
+                    stopwords.contains(collapsed)
+                        || lemma::lemmatizer().contains(collapsed, self.lang)
+                })
+            });
+        }
+
+        if self.config.fold_leet {
+            apply(word, slang::fold_leet);
+        }
+    }
+
+    /// Transliterates a word and folds its accents, if enabled.
+    fn script(&self, word: &mut Cow<'_, str>) {
+        if let Some(scheme) = self.config.transliterate {
+            apply(word, |word| {
+                transliterate::to_latin(word, scheme, self.lang)
+            });
+        }
+
+        if self.config.fold_accents {
+            apply(word, normalize::fold_accents);
+        }
+    }
+
+    /// Key of a phrase from its words as written, separated by a space.
+    ///
+    /// Words are processed as when they are counted alone, but neither
+    /// lemmatized nor stemmed, which would alter names such as "Simone".
+    fn phrase_key(&self, words: &str, stopwords: &StopWords) -> String {
+        words
+            .split(' ')
+            .map(|word| {
+                let mut word = self.clean(word);
+                self.spell(&mut word, stopwords);
+                self.script(&mut word);
+                word
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Replaces a word by the result of a step of the pipeline, only copying it
+/// if the step changed it.
+fn apply<'a>(word: &mut Cow<'a, str>, step: impl FnOnce(&str) -> Cow<'_, str>) {
+    let changed = match step(word) {
+        Cow::Owned(changed) => Some(changed),
+        // Some steps borrow another text, such as a lemma.
+        Cow::Borrowed(changed) if changed != word.as_ref() => Some(changed.to_string()),
+        Cow::Borrowed(_) => None,
+    };
+
+    if let Some(changed) = changed {
+        *word = Cow::Owned(changed);
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let stopwords = self
+            .stopwords
+            .take()
+            .unwrap_or_else(|| stopwords::registry().get(self.service, self.lang));
+        let text = self.text;
+
+        while self.pending.is_empty() && self.position < text.len() {
+            let rest = &self.text[self.position..];
+            let start = self.position
+                + rest
+                    .find(|c: char| !self.is_separator(c))
+                    .unwrap_or(rest.len());
+            // Numbers with spaced groups of thousands, as "1 000", are read
+            // whole.
+            let number = start + number::spaced_length(&text[start..]).unwrap_or_default();
+            let end = self.text[number..]
+                .find(|c: char| self.is_separator(c))
+                .map_or(self.text.len(), |end| number + end);
+            self.position = end;
+
+            let chunk = &text[start..end];
+            if self.config.apostrophe != Apostrophe::Elide || !chunk.contains(elision::APOSTROPHES)
+            {
+                self.push(start..end, &stopwords);
+            } else {
+                for piece in elision::split(chunk, self.lang) {
+                    let range = start + piece.range.start..start + piece.range.end;
+
+                    match piece.expansion {
+                        Some(expansion) => {
+                            if let Some(token) = self.process(expansion, range.start, &stopwords) {
+                                let token = Token {
+                                    byte_range: range,
+                                    ..token
+                                };
+                                self.queue(token, &stopwords);
+                            }
+                        }
+                        None => self.push(range, &stopwords),
+                    }
+                }
+            }
+        }
+
+        if self.pending.is_empty() && self.config.phrases {
+            if let Some(phrase) = self.phrases.finish(self.text) {
+                self.push_phrase(phrase, &stopwords);
+            }
+        }
+
+        self.stopwords = Some(stopwords);
+        self.pending.pop_front()
+    }
+}
+
+/// Returns the default settings, shared by every [`tokens`] call.
+fn default_config() -> &'static TokenizerConfig {
+    static DEFAULT: OnceLock<TokenizerConfig> = OnceLock::new();
+
+    DEFAULT.get_or_init(TokenizerConfig::default)
+}
+
+/// Splits a text into [`Token`]s.
+///
+/// Only built-in stop words are removed until custom ones are loaded with
+/// [`stopwords::init`], or registered for a [service](Tokens::service).
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::{tokens, TokenKind};
+///
+/// let hashtags: Vec<_> = tokens("L'IVG dans la Constitution ! #8mars")
+///     .filter(|token| token.kind == TokenKind::Hashtag)
+///     .map(|token| token.text)
+///     .collect();
+///
+/// assert_eq!(hashtags, vec!["#8mars".to_string()]);
+/// ```
+pub fn tokens(text: &str) -> Tokens<'_> {
+    Tokens {
+        text,
+        position: 0,
+        service: "",
+        lang: "",
+        config: Cow::Borrowed(default_config()),
+        stopwords: None,
+        phrases: Phrases::default(),
+        pending: VecDeque::new(),
+    }
+}
+
+/// Checks that a text can be tokenized with a configuration.
+///
+/// Returns an error if the text is longer than
+/// [`TokenizerConfig::max_bytes`] or contains control characters other than
+/// whitespace, such as binary data.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::{check, config::TokenizerConfig};
+///
+/// let config = TokenizerConfig::default();
+///
+/// assert!(check("Bravo !", &config).is_ok());
+/// assert!(check("Bravo\0", &config).is_err());
+/// ```
+pub fn check(text: &str, config: &TokenizerConfig) -> Result<(), Error> {
+    if text.len() > config.max_bytes {
+        return Err(Error::new(
+            ErrorType::Tokenizer(TokenizerError::TooLong),
+            None,
+            Some(format!(
+                "text of {} bytes, over the limit of {} bytes",
+                text.len(),
+                config.max_bytes
+            )),
+        ));
+    }
+
+    if let Some(position) = text.find(|c: char| c.is_control() && !c.is_whitespace()) {
+        return Err(Error::new(
+            ErrorType::Tokenizer(TokenizerError::InvalidCharacter),
+            None,
+            Some(format!("control character at byte {}", position)),
+        ));
+    }
 
-    Ok(normalize.trim_end().to_string())
+    Ok(())
+}
+
+/// Normalize and lowercase words, remove punctuation and separate words into tokens.
+///
+/// Returns an error if the text does not pass [`check`] or if no word is
+/// left after processing.
+pub fn tokenize<T: ToString>(text: T) -> Result<String, Error> {
+    let text = text.to_string();
+    let tokens = tokens(&text);
+    check(&text, &tokens.config)?;
+
+    let mut words = String::with_capacity(text.len());
+    for token in tokens {
+        if !words.is_empty() {
+            words.push(' ');
+        }
+        words.push_str(&token.text);
+    }
+
+    if words.is_empty() {
+        return Err(Error::new(
+            ErrorType::Tokenizer(TokenizerError::Empty),
+            None,
+            None,
+        ));
+    }
+
+    Ok(words)
 }
 
 #[cfg(test)]
@@ -51,4 +757,465 @@ mod tests {
             "really like apples but prefer gravitalia sometimes yeah"
         )
     }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let error = tokenize("? ! ...").unwrap_err();
+        assert!(matches!(
+            error.etype,
+            ErrorType::Tokenizer(TokenizerError::Empty)
+        ));
+
+        let error = tokenize("GIF89a\u{1}\u{0}").unwrap_err();
+        assert!(matches!(
+            error.etype,
+            ErrorType::Tokenizer(TokenizerError::InvalidCharacter)
+        ));
+
+        let config = TokenizerConfig {
+            max_bytes: 4,
+            ..Default::default()
+        };
+        let error = check("Bravo", &config).unwrap_err();
+        assert!(matches!(
+            error.etype,
+            ErrorType::Tokenizer(TokenizerError::TooLong)
+        ));
+        assert!(check("Bravo\r\n\tà tous", &TokenizerConfig::default()).is_ok());
+    }
+
+    #[test]
+    fn test_tokens() {
+        let plaintext = "Merci @FranceInsoumise pour le #8mars (2024)!";
+        let tokens: Vec<Token> = tokens(plaintext).collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_ref(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("merci", TokenKind::Word),
+                ("@franceinsoumise", TokenKind::Mention),
+                ("pour", TokenKind::Word),
+                ("le", TokenKind::Word),
+                ("#8mars", TokenKind::Hashtag),
+                ("2024", TokenKind::Number),
+            ]
+        );
+        assert_eq!(&plaintext[tokens[1].byte_range.clone()], "@FranceInsoumise");
+        assert_eq!(&plaintext[tokens[5].byte_range.clone()], "2024");
+    }
+
+    #[test]
+    #[cfg(feature = "fr")]
+    fn test_lang() {
+        let plaintext = "Une tendre pensée pour Simone Veil";
+        let tokens: Vec<String> = tokens(plaintext)
+            .lang("fr")
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(tokens, vec!["tendre", "pensée", "simone", "veil"]);
+    }
+
+    #[test]
+    #[cfg(feature = "fr")]
+    fn test_stem() {
+        let plaintext = "Manifestation, manifestations et #manifestations";
+        let tokens: Vec<String> = tokens(plaintext)
+            .lang("fr")
+            .stem(true)
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(tokens, vec!["manifest", "manifest", "#manifestations"]);
+    }
+
+    #[test]
+    #[cfg(feature = "fr")]
+    fn test_stopword_keys() {
+        let path = std::env::temp_dir().join("squid_test_stopword_keys");
+        std::fs::write(&path, "manifest\n").unwrap();
+        stopwords::registry()
+            .register("test-stopword-keys", path.clone())
+            .unwrap();
+
+        let tokens: Vec<String> = tokens("Manifestations et défilés")
+            .service("test-stopword-keys")
+            .lang("fr")
+            .stem(true)
+            .map(|token| token.text.into_owned())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(tokens, vec!["défil"]);
+    }
+
+    #[test]
+    fn test_lemmatize() {
+        // A language of its own, not to interfere with other tests.
+        lemma::lemmatizer().extend("test-lemmatize", [("Allons", "aller"), ("vont", "aller")]);
+        let tokens: Vec<String> = tokens("Nous allons, ils vont")
+            .lang("test-lemmatize")
+            .lemmatize(true)
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(tokens, vec!["nous", "aller", "ils", "aller"]);
+    }
+
+    #[test]
+    #[cfg(feature = "fr")]
+    fn test_segment_hashtags() {
+        let plaintext = "Bravo ! #JourneeDesDroitsDesFemmes";
+        let tokens: Vec<Token> = tokens(plaintext)
+            .lang("fr")
+            .segment_hashtags(true)
+            .collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_ref(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bravo", TokenKind::Word),
+                ("#journeedesdroitsdesfemmes", TokenKind::Hashtag),
+                ("journee", TokenKind::Word),
+                ("droits", TokenKind::Word),
+                ("femmes", TokenKind::Word),
+            ]
+        );
+        assert_eq!(&plaintext[tokens[3].byte_range.clone()], "Droits");
+    }
+
+    #[test]
+    fn test_display() {
+        let tokens: Vec<(String, String)> = tokens("L’#IVG, don't (Paris)!")
+            .map(|token| (token.text.into_owned(), token.display.into_owned()))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("#ivg".to_string(), "#IVG".to_string()),
+                ("do".to_string(), "do".to_string()),
+                ("not".to_string(), "not".to_string()),
+                ("paris".to_string(), "Paris".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_phrases() {
+        let plaintext = "Concert d’Aya Nakamura et de Megan Thee Stallion !";
+        let tokens: Vec<Token> = tokens(plaintext).phrases(true).collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|token| token.kind == TokenKind::Phrase)
+                .map(|token| (token.text.as_ref(), token.display.as_ref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("aya nakamura", "Aya Nakamura"),
+                ("megan thee stallion", "Megan Thee Stallion"),
+            ]
+        );
+        assert_eq!(tokens[3].text, "aya nakamura");
+    }
+
+    #[test]
+    fn test_phrase_keys() {
+        profanity::lexicon().extend("test-phrases", ["connard"]);
+        let plaintext = "vive Jean Connaaard et Aya Nakamuraaa ou Μαρία Κάλλας";
+        let tokens: Vec<(String, bool)> = tokens(plaintext)
+            .lang("test-phrases")
+            .phrases(true)
+            .collapse_repeats(true)
+            .transliterate(Some(Scheme::Bgn))
+            .flag_profanity(true)
+            .filter(|token| token.kind == TokenKind::Phrase)
+            .map(|token| (token.text.into_owned(), token.flagged))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("jean connaard".to_string(), true),
+                ("aya nakamuraa".to_string(), false),
+                ("maria kallas".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transliterate() {
+        let plaintext = "Зеленский Τσιτσιπάς #Москва";
+        let tokens: Vec<Token> = tokens(plaintext).transliterate(Some(Scheme::Bgn)).collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_ref(), token.display.as_ref()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("zelenskiy", "Зеленский"),
+                ("tsitsipas", "Τσιτσιπάς"),
+                ("#moskva", "#Москва"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_accents() {
+        let plaintext = "Journée JOURNÉE journee";
+        let tokens: Vec<Token> = tokens(plaintext).fold_accents(true).collect();
+
+        assert!(tokens.iter().all(|token| token.text == "journee"));
+        assert_eq!(&plaintext[tokens[1].byte_range.clone()], "JOURNÉE");
+    }
+
+    #[test]
+    fn test_links() {
+        let plaintext =
+            "Écrivez sur contact@gravitalia.com ou @Gravitalia (https://www.Gravitalia.com/about).";
+
+        assert_eq!(
+            tokens(plaintext)
+                .map(|token| (token.text.into_owned(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("écrivez".to_string(), TokenKind::Word),
+                ("sur".to_string(), TokenKind::Word),
+                ("contact@gravitalia.com".to_string(), TokenKind::Email),
+                ("ou".to_string(), TokenKind::Word),
+                ("@gravitalia".to_string(), TokenKind::Mention),
+                (
+                    "https://www.gravitalia.com/about".to_string(),
+                    TokenKind::Url
+                ),
+            ]
+        );
+
+        let tokens: Vec<String> = tokens(plaintext)
+            .urls(Policy::Domain)
+            .mentions(Policy::Drop)
+            .emails(Policy::Drop)
+            .map(|token| token.text.into_owned())
+            .collect();
+        assert_eq!(tokens, vec!["écrivez", "sur", "ou", "gravitalia.com"]);
+
+        let links: Vec<(String, TokenKind)> = super::tokens("Lu sur lemonde.fr/politique.")
+            .urls(Policy::Domain)
+            .map(|token| (token.text.into_owned(), token.kind))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("lu".to_string(), TokenKind::Word),
+                ("sur".to_string(), TokenKind::Word),
+                ("lemonde.fr".to_string(), TokenKind::Url),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let plaintext = "Le 1er défilé du #8mars, 8Mars : 1,5k personnes et 10K vues (2024)";
+
+        assert_eq!(
+            tokens(plaintext)
+                .numbers(number::Policy::Normalize)
+                .map(|token| (token.text.into_owned(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("le".to_string(), TokenKind::Word),
+                ("1".to_string(), TokenKind::Number),
+                ("défilé".to_string(), TokenKind::Word),
+                ("du".to_string(), TokenKind::Word),
+                ("#8mars".to_string(), TokenKind::Hashtag),
+                ("8 mars".to_string(), TokenKind::Date),
+                ("1500".to_string(), TokenKind::Number),
+                ("personnes".to_string(), TokenKind::Word),
+                ("et".to_string(), TokenKind::Word),
+                ("10000".to_string(), TokenKind::Number),
+                ("vues".to_string(), TokenKind::Word),
+                ("2024".to_string(), TokenKind::Number),
+            ]
+        );
+
+        let tokens: Vec<String> = tokens(plaintext)
+            .numbers(number::Policy::Drop)
+            .map(|token| token.text.into_owned())
+            .collect();
+        assert_eq!(
+            tokens,
+            vec!["le", "défilé", "du", "#8mars", "personnes", "et", "vues"]
+        );
+
+        // Groups of thousands share the key of the number, and a single
+        // comma is read as the language writes it.
+        for (text, lang, number) in [
+            ("1,000 vues", "en", "1000"),
+            ("1.000 vues", "fr", "1000"),
+            ("1 000 vues", "fr", "1000"),
+            ("1\u{202F}000\u{202F}000 vues", "fr", "1000000"),
+            ("1,000,000 vues", "fr", "1000000"),
+            ("1,000 vues", "fr", "1.000"),
+            ("1,5 vues", "fr", "1.5"),
+            ("1,5 vues", "en", "1.5"),
+        ] {
+            assert_eq!(
+                super::tokens(text)
+                    .lang(lang)
+                    .numbers(number::Policy::Normalize)
+                    .map(|token| (token.text.into_owned(), token.kind))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (number.to_string(), TokenKind::Number),
+                    ("vues".to_string(), TokenKind::Word),
+                ],
+                "{}",
+                text
+            );
+        }
+
+        // Scores are neither glued nor dropped as punctuation.
+        assert_eq!(
+            super::tokens("victoire 3-1 !")
+                .map(|token| (token.text.into_owned(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("victoire".to_string(), TokenKind::Word),
+                ("3-1".to_string(), TokenKind::Number),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_elision() {
+        assert_eq!(
+            tokenize("L’IVG jusqu'aujourd’hui, don't!").unwrap(),
+            "ivg aujourd'hui do not"
+        );
+    }
+
+    #[test]
+    fn test_slang() {
+        // Lemmas are known words without a row mapping them to themselves.
+        let path = std::env::temp_dir().join("squid_test_slang.tsv");
+        std::fs::write(&path, "# form\tlemma\nsos\tso\n").unwrap();
+        lemma::lemmatizer()
+            .load("test-slang", &path, lemma::TableFormat::default())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let tokens: Vec<String> = tokens("Loooool gr8 m4cr0n s@lut 2000 soooo www.")
+            .lang("test-slang")
+            .collapse_repeats(true)
+            .fold_leet(true)
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec!["lol", "great", "macron", "salut", "2000", "so", "www"]
+        );
+    }
+
+    #[test]
+    fn test_flag_profanity() {
+        profanity::lexicon().extend("test", ["connard"]);
+        let tokens: Vec<(String, bool)> = tokens("Quel CONNAAARD @connard")
+            .lang("test")
+            .flag_profanity(true)
+            .map(|token| (token.text.into_owned(), token.flagged))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("quel".to_string(), false),
+                ("connaaard".to_string(), true),
+                ("@connard".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config = TokenizerConfig {
+            punctuation: vec!['!', '.'],
+            min_length: 1,
+            apostrophe: Apostrophe::Keep,
+            remove_stopwords: false,
+            ..Default::default()
+        };
+        let tokens: Vec<String> = tokens("Aujourd'hui, à Paris!")
+            .config(&config)
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(tokens, vec!["aujourd'hui,", "à", "paris"]);
+    }
+
+    #[test]
+    fn test_emoji() {
+        let plaintext = "🚨De nombreux artistes ☺️☺ 👩🏽‍🚀!";
+        let tokens: Vec<Token> = tokens(plaintext).collect();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| (token.text.as_ref(), token.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("🚨", TokenKind::Emoji),
+                ("de", TokenKind::Word),
+                ("nombreux", TokenKind::Word),
+                ("artistes", TokenKind::Word),
+                // Without selector, "☺" is text.
+                ("☺\u{fe0f}", TokenKind::Emoji),
+                ("👩🏽\u{200d}🚀", TokenKind::Emoji),
+            ]
+        );
+        assert_eq!(&plaintext[tokens[4].byte_range.clone()], "☺️");
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(
+            tokenize("Bravo\u{202F}! 我爱北京\u{A0}2024").unwrap(),
+            "bravo 我 爱 北 京 2024"
+        );
+    }
+
+    #[test]
+    fn test_unspaced_links() {
+        let tokens: Vec<(String, TokenKind)> =
+            tokens("#東京オリンピック @東京都庁 https://ja.wikipedia.org/wiki/東京 東京")
+                .map(|token| (token.text.into_owned(), token.kind))
+                .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("#東京オリンピック".to_string(), TokenKind::Hashtag),
+                ("@東京都庁".to_string(), TokenKind::Mention),
+                (
+                    "https://ja.wikipedia.org/wiki/東京".to_string(),
+                    TokenKind::Url
+                ),
+                ("東".to_string(), TokenKind::Word),
+                ("京".to_string(), TokenKind::Word),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(tokenize("Mbappé ＧＯＡＬ 🚨").unwrap(), "mbappé goal 🚨");
+    }
 }
@@ -0,0 +1,167 @@
+//! recognition of URLs, mentions and e-mail addresses.
+//!
+//! Punctuation is meaningful inside of them, so they are recognized before
+//! punctuation is removed from words.
+
+/// What to do with URLs, mentions or e-mail addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// Removes them from tokens.
+    Drop,
+    /// Keeps them as written, lowercased.
+    #[default]
+    Keep,
+    /// Keeps only the domain name of URLs and e-mail addresses, so links to
+    /// different articles of a website share the same key. Same as
+    /// [`Policy::Keep`] for mentions.
+    Domain,
+}
+
+/// Top-level domains of domain names written without scheme, `www.` nor
+/// path, such as `lemonde.fr`. Other words containing a dot, such as file
+/// names or sentences missing a space, are not links.
+///
+/// Top-level domains which are also French or English words, such as "de",
+/// "me" or "it", are left out, since "mars.De" is more likely a missing space
+/// than a link.
+const TLDS: [&str; 59] = [
+    "ai", "app", "ar", "bio", "biz", "blog", "br", "cc", "ch", "cl", "cn", "co", "com", "cz",
+    "dev", "dk", "dz", "edu", "eu", "fi", "fm", "fr", "gg", "gov", "gr", "ie", "info", "int", "io",
+    "jp", "kr", "live", "ly", "mx", "net", "news", "nl", "nz", "online", "org", "paris", "pl",
+    "pt", "re", "ro", "ru", "shop", "site", "sn", "store", "tech", "tn", "tr", "tv", "ua", "uk",
+    "xyz", "za", "zone",
+];
+
+/// Returns the domain name of a URL starting with `http://`, `https://` or
+/// `www.`, without `www.`, or of a domain name followed by a path. Domain
+/// names alone are only links with a known top-level domain, written in
+/// lowercase.
+///
+/// Returns [`None`] if the text is not a URL.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::link::url_domain;
+///
+/// assert_eq!(url_domain("https://www.lemonde.fr/politique/"), Some("lemonde.fr"));
+/// assert_eq!(url_domain("www.gravitalia.com"), Some("gravitalia.com"));
+/// assert_eq!(url_domain("lemonde.fr/politique"), Some("lemonde.fr"));
+/// assert_eq!(url_domain("lemonde.fr"), Some("lemonde.fr"));
+/// assert_eq!(url_domain("lemonde"), None);
+/// assert_eq!(url_domain("photo.jpg"), None);
+/// ```
+pub fn url_domain(text: &str) -> Option<&str> {
+    if let Some(rest) = ["https://", "http://"]
+        .iter()
+        .find_map(|scheme| strip_prefix_ignore_case(text, scheme))
+    {
+        let host = host(rest);
+        let host = strip_prefix_ignore_case(host, "www.").unwrap_or(host);
+
+        let valid = !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '.');
+        valid.then_some(host)
+    } else if let Some(rest) = strip_prefix_ignore_case(text, "www.") {
+        Some(host(rest)).filter(|host| is_domain(host))
+    } else {
+        let host = host(text);
+        let tld = host.rsplit('.').next().unwrap_or_default();
+        let path = text[host.len()..].starts_with('/');
+
+        (is_domain(host) && (path || TLDS.contains(&tld))).then_some(host)
+    }
+}
+
+/// Returns the domain name of an e-mail address.
+///
+/// Returns [`None`] if the text is not an e-mail address.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::link::email_domain;
+///
+/// assert_eq!(email_domain("contact@gravitalia.com"), Some("gravitalia.com"));
+/// assert_eq!(email_domain("@gravitalia"), None);
+/// ```
+pub fn email_domain(text: &str) -> Option<&str> {
+    let (local, domain) = text.split_once('@')?;
+
+    let valid_local = !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "!#$%&*+-./=?^_`{|}~".contains(c));
+
+    if valid_local && is_domain(domain) {
+        Some(domain)
+    } else {
+        None
+    }
+}
+
+/// Whether a text is a domain name with a top-level domain, such as
+/// `lemonde.fr`.
+fn is_domain(text: &str) -> bool {
+    let mut labels = text.split('.');
+    let tld = labels.next_back().unwrap_or_default();
+
+    text.contains('.')
+        && tld.chars().count() >= 2
+        && tld.chars().all(char::is_alphabetic)
+        && labels.all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+/// Returns the host of a URL without its scheme.
+fn host(url: &str) -> &str {
+    &url[..url.find(['/', '?', '#', ':']).unwrap_or(url.len())]
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_domain() {
+        assert_eq!(
+            url_domain("HTTPS://Twitter.com/i/status/1"),
+            Some("Twitter.com")
+        );
+        assert_eq!(url_domain("http://localhost:8080/"), Some("localhost"));
+        assert_eq!(url_domain("www.lemonde"), None);
+        assert_eq!(url_domain("LeMonde.fr"), Some("LeMonde.fr"));
+        assert_eq!(url_domain("LeMonde.FR/"), Some("LeMonde.FR"));
+        assert_eq!(url_domain("elysee.gouv.fr?page=1"), Some("elysee.gouv.fr"));
+        assert_eq!(url_domain("index.html"), None);
+        assert_eq!(url_domain("fin.Bravo"), None);
+        for typo in [
+            "mars.Au",
+            "Merci.De",
+            "fin.It",
+            "ok.Me",
+            "vu.de",
+            "fin.FR",
+            "Merci.De?",
+        ] {
+            assert_eq!(url_domain(typo), None, "{}", typo);
+        }
+        assert_eq!(url_domain("https://"), None);
+    }
+
+    #[test]
+    fn test_email_domain() {
+        assert_eq!(email_domain("jean.dupont+squid@mail.fr"), Some("mail.fr"));
+        assert_eq!(email_domain("contact@gravitalia"), None);
+        assert_eq!(email_domain("a@b@c.fr"), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! unicode normalization of words.
+
+use std::borrow::Cow;
+use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+/// Applies NFKC normalization then lowercases the text.
+///
+/// Compatibility characters are folded into their canonical form, so full-width
+/// letters become ASCII and ligatures are split. Texts already normalized are
+/// borrowed.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::normalize::normalize;
+///
+/// assert_eq!(normalize("MBAPPÉ"), "mbappé");
+/// assert_eq!(normalize("Ｇｒａｖｉｔａｌｉａ"), "gravitalia");
+/// ```
+pub fn normalize(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return if text.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            Cow::Owned(text.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(text)
+        };
+    }
+
+    let lowercase = text.chars().all(|c| {
+        let mut lowercase = c.to_lowercase();
+        lowercase.next() == Some(c) && lowercase.next().is_none()
+    });
+    if lowercase && is_nfkc_quick(text.chars()) == IsNormalized::Yes {
+        return Cow::Borrowed(text);
+    }
+
+    Cow::Owned(text.nfkc().collect::<String>().to_lowercase())
+}
+
+/// Removes diacritics and expands ligatures.
+///
+/// The text is decomposed (NFD) and the accents of Latin letters are dropped.
+/// Letters of other scripts keep their marks, since they often tell distinct
+/// letters apart, as "й" and "и". Letters that do not decompose, such as `œ`
+/// or `ß`, are replaced by their usual spelling.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::normalize::fold_accents;
+///
+/// assert_eq!(fold_accents("journée"), "journee");
+/// assert_eq!(fold_accents("cœur"), "coeur");
+/// assert_eq!(fold_accents("straße"), "strasse");
+/// assert_eq!(fold_accents("Йошкар"), "Йошкар");
+/// ```
+pub fn fold_accents(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut latin = false;
+
+    for c in text.nfd() {
+        if is_diacritic(c) {
+            if !latin {
+                result.push(c);
+            }
+            continue;
+        }
+        latin = is_latin(c);
+
+        match c {
+            'œ' => result.push_str("oe"),
+            'Œ' => result.push_str("OE"),
+            'æ' => result.push_str("ae"),
+            'Æ' => result.push_str("AE"),
+            'ß' => result.push_str("ss"),
+            'ẞ' => result.push_str("SS"),
+            'þ' => result.push_str("th"),
+            'Þ' => result.push_str("TH"),
+            'ø' => result.push('o'),
+            'Ø' => result.push('O'),
+            'đ' | 'ð' => result.push('d'),
+            'Đ' | 'Ð' => result.push('D'),
+            'ł' => result.push('l'),
+            'Ł' => result.push('L'),
+            'ı' => result.push('i'),
+            _ => result.push(c),
+        }
+    }
+
+    // Recomposes letters of other scripts.
+    Cow::Owned(result.nfc().collect())
+}
+
+/// Whether a character is a Latin letter, whose accents may be dropped.
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphabetic()
+        || (c.is_alphabetic()
+            && matches!(
+                c,
+                '\u{c0}'..='\u{24f}'
+                    | '\u{1e00}'..='\u{1eff}'
+                    | '\u{2c60}'..='\u{2c7f}'
+                    | '\u{a720}'..='\u{a7ff}'
+            ))
+}
+
+/// Whether a character is an accent, from the combining diacritical marks
+/// block.
+///
+/// Other combining marks are vowels or signs of their script, or part of an
+/// emoji, and cannot be dropped.
+fn is_diacritic(c: char) -> bool {
+    matches!(c, '\u{300}'..='\u{36f}')
+}
+
+/// Decodes words stored with the legacy `\u{e9}` escaped encoding.
+///
+/// Older versions of the tokenizer replaced every non-ASCII character by its
+/// escape sequence. Texts without escape sequences are returned unchanged.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::normalize::unescape_legacy;
+///
+/// assert_eq!(unescape_legacy("mbapp\\u{e9}"), "mbappé");
+/// assert_eq!(unescape_legacy("gravitalia"), "gravitalia");
+/// ```
+pub fn unescape_legacy(text: &str) -> Cow<'_, str> {
+    if !text.contains("\\u{") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(position) = rest.find("\\u{") {
+        result.push_str(&rest[..position]);
+        rest = &rest[position..];
+
+        let decoded = rest[3..].find('}').and_then(|end| {
+            u32::from_str_radix(&rest[3..3 + end], 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| (c, 3 + end + 1))
+        });
+
+        match decoded {
+            Some((c, length)) => {
+                result.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                result.push_str("\\u{");
+                rest = &rest[3..];
+            }
+        }
+    }
+    result.push_str(rest);
+
+    Cow::Owned(result.nfc().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_accents() {
+        assert_eq!(fold_accents("JOURNÉE journée"), "JOURNEE journee");
+        assert_eq!(fold_accents("Łódź Ærø"), "Lodz AEro");
+        assert_eq!(fold_accents("🚨 ❤️"), "🚨 ❤️");
+        assert_eq!(fold_accents("Αθήνα Йошкар"), "Αθήνα Йошкар");
+        assert_eq!(fold_accents("Йошкар-Ола́ café"), "Йошкар-Ола́ cafe");
+        assert_eq!(fold_accents("รัก नमस्ते كَتَبَ"), "รัก नमस्ते كَتَبَ");
+    }
+
+    #[test]
+    fn test_unescape_legacy() {
+        assert_eq!(
+            unescape_legacy("#journ\\u{e9}e \\u{1f6a8} d\\u{e9}j\\u{e0}"),
+            "#journée 🚨 déjà"
+        );
+        assert_eq!(unescape_legacy("broken\\u{zz}"), "broken\\u{zz}");
+    }
+}
@@ -0,0 +1,573 @@
+//! recognition of numbers, ordinals, quantities and dates.
+//!
+//! Words such as "2024", "1er", "10k" or "8mars" are recognized before
+//! punctuation is removed, so "1,5k" is not read as "15k" nor the score "3-1"
+//! as "31". They can be kept as written, dropped, or normalized so "8mars"
+//! and "8 mars", "10k" and "10000" or "1,000", "1 000" and "1000" share the
+//! same key. Hashtags, such as "#8mars", are left alone.
+
+use crate::{normalize, TokenKind};
+use std::borrow::Cow;
+
+/// What to do with numbers and dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// Removes them from tokens.
+    Drop,
+    /// Keeps them as written, lowercased.
+    #[default]
+    Keep,
+    /// Replaces them by their [`Numeral::normalized`] form.
+    Normalize,
+}
+
+/// A recognized number or date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Numeral {
+    /// Either [`TokenKind::Number`] or [`TokenKind::Date`].
+    pub kind: TokenKind,
+    /// Usual spelling, such as "8 mars" for "8mars", "1" for "1er",
+    /// "10000" for "10k" or "1000.5" for "1.000,5".
+    pub normalized: String,
+}
+
+/// Months of each language, by ISO 639-1 code. The first name of each month
+/// is its full name, followed by abbreviations. Names are compared without
+/// accents.
+const MONTHS: [(&str, [&[&str]; 12]); 5] = [
+    (
+        "de",
+        [
+            &["januar", "jan"],
+            &["februar", "feb"],
+            &["marz"],
+            &["april", "apr"],
+            &["mai"],
+            &["juni"],
+            &["juli"],
+            &["august", "aug"],
+            &["september", "sep", "sept"],
+            &["oktober", "okt"],
+            &["november", "nov"],
+            &["dezember", "dez"],
+        ],
+    ),
+    (
+        "en",
+        [
+            &["january", "jan"],
+            &["february", "feb"],
+            &["march", "mar"],
+            &["april", "apr"],
+            &["may"],
+            &["june", "jun"],
+            &["july", "jul"],
+            &["august", "aug"],
+            &["september", "sep", "sept"],
+            &["october", "oct"],
+            &["november", "nov"],
+            &["december", "dec"],
+        ],
+    ),
+    (
+        "es",
+        [
+            &["enero", "ene"],
+            &["febrero"],
+            &["marzo"],
+            &["abril"],
+            &["mayo"],
+            &["junio"],
+            &["julio"],
+            &["agosto", "ago"],
+            &["septiembre"],
+            &["octubre"],
+            &["noviembre"],
+            &["diciembre", "dic"],
+        ],
+    ),
+    (
+        "fr",
+        [
+            &["janvier", "janv"],
+            &["fevrier", "fevr", "fev"],
+            &["mars"],
+            &["avril", "avr"],
+            &["mai"],
+            &["juin"],
+            &["juillet", "juil"],
+            &["aout"],
+            &["septembre", "sept"],
+            &["octobre", "oct"],
+            &["novembre", "nov"],
+            &["decembre", "dec"],
+        ],
+    ),
+    (
+        "it",
+        [
+            &["gennaio", "gen"],
+            &["febbraio"],
+            &["marzo"],
+            &["aprile"],
+            &["maggio"],
+            &["giugno"],
+            &["luglio"],
+            &["agosto"],
+            &["settembre", "set"],
+            &["ottobre", "ott"],
+            &["novembre"],
+            &["dicembre"],
+        ],
+    ),
+];
+
+/// Suffixes of ordinal numbers, after normalization and without accents.
+const ORDINALS: [&str; 14] = [
+    "er", "re", "ere", "e", "eme", "nd", "nde", "st", "rd", "th", "o", "a", "°", "ieme",
+];
+
+/// Multipliers of quantities, such as "k" in "10k". "m" is left out, as
+/// "100m" and "5m" are rather metres and minutes than millions.
+const MULTIPLIERS: [(&str, u128); 4] = [
+    ("k", 1_000),
+    ("md", 1_000_000_000),
+    ("mds", 1_000_000_000),
+    ("bn", 1_000_000_000),
+];
+
+/// Languages writing decimals after a comma, as "1,5", and separating groups
+/// of thousands with points, as "1.000". Others write "1.5" and "1,000".
+const DECIMAL_COMMA: [&str; 30] = [
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv",
+    "nb", "nl", "nn", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+/// Spaces separating groups of thousands, as in "1 000". No-break spaces
+/// are replaced by spaces when normalized.
+const GROUP_SPACES: [char; 3] = [' ', '\u{A0}', '\u{202F}'];
+
+/// Units kept after numbers, such as "%" in "50%".
+const UNITS: [char; 5] = ['%', '€', '$', '£', '¥'];
+
+/// Recognizes a number, an ordinal, a quantity or a date.
+///
+/// The language, as an ISO 639-1 code, is preferred to name months, so
+/// "25dec" gives "25 decembre" in French and "25 december" otherwise.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::{number::parse, TokenKind};
+///
+/// let date = parse("8Mars", "fr").unwrap();
+/// assert_eq!((date.kind, date.normalized.as_str()), (TokenKind::Date, "8 mars"));
+///
+/// assert_eq!(parse("1er", "fr").unwrap().normalized, "1");
+/// assert_eq!(parse("1,5k", "fr").unwrap().normalized, "1500");
+/// assert_eq!(parse("1,000", "en").unwrap().normalized, "1000");
+/// assert_eq!(parse("1 000", "fr").unwrap().normalized, "1000");
+/// assert_eq!(parse("2024", "fr").unwrap().kind, TokenKind::Number);
+/// assert!(parse("covid19", "fr").is_none() && parse("4g", "fr").is_none());
+/// ```
+pub fn parse(text: &str, lang: &str) -> Option<Numeral> {
+    if !text.starts_with(|c: char| c.is_ascii_digit() || UNITS.contains(&c)) {
+        return None;
+    }
+
+    let text = normalize::normalize(text);
+    let text = join_groups(&text);
+    let number = |normalized: String| {
+        Some(Numeral {
+            kind: TokenKind::Number,
+            normalized,
+        })
+    };
+
+    // Amount of money, such as "$5".
+    if let Some(amount) = text.strip_prefix(UNITS) {
+        let unit = &text[..text.len() - amount.len()];
+        return decimal(amount, lang)
+            .map(|amount| format!("{}{}", unit, amount))
+            .and_then(number);
+    }
+
+    if let Some(date) = numeric_date(&text) {
+        return Some(Numeral {
+            kind: TokenKind::Date,
+            normalized: date,
+        });
+    }
+
+    if let Some(score) = score(&text) {
+        return number(score);
+    }
+
+    let length = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(text.len());
+    let (value, suffix) = text.split_at(length);
+    split_decimal(value, lang)?;
+    let integer = value.bytes().all(|byte| byte.is_ascii_digit());
+
+    if suffix.is_empty() || suffix.chars().all(|c| UNITS.contains(&c)) {
+        return number(format!("{}{}", decimal(value, lang)?, suffix));
+    }
+
+    if integer && ORDINALS.contains(&normalize::fold_accents(suffix).as_ref()) {
+        // Without leading zeros, but "00th" is still "0".
+        let digits = value.trim_start_matches('0');
+        return number(if digits.is_empty() { "0" } else { digits }.to_string());
+    }
+
+    if let Some((_, multiplier)) = MULTIPLIERS.iter().find(|(name, _)| *name == suffix) {
+        // Too large quantities are kept as written.
+        return number(quantity(value, *multiplier, lang).unwrap_or_else(|| text.to_string()));
+    }
+
+    // Normalized dates, such as "8 mars", are recognized too.
+    let day: u8 = value.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let month = month(suffix.strip_prefix(' ').unwrap_or(suffix), lang)?;
+
+    Some(Numeral {
+        kind: TokenKind::Date,
+        normalized: format!("{} {}", day, month),
+    })
+}
+
+/// Returns the kind of a number or a date, if the text is one.
+pub fn kind(text: &str) -> Option<TokenKind> {
+    parse(text, "").map(|numeral| numeral.kind)
+}
+
+/// Returns the length of a number written with spaces between its groups
+/// of thousands at the start of a text, such as "1 000 000" in
+/// "1 000 000 vues", so it is read as a single word.
+///
+/// Returns [`None`] if the text does not start with such a number.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::number::spaced_length;
+///
+/// assert_eq!(spaced_length("1 000 vues"), Some(5));
+/// assert_eq!(spaced_length("8 mars"), None);
+/// assert_eq!(spaced_length("1 0000"), None);
+/// ```
+pub fn spaced_length(text: &str) -> Option<usize> {
+    let digits = |text: &str| text.bytes().take_while(u8::is_ascii_digit).count();
+
+    let mut length = digits(text);
+    if !(1..=3).contains(&length) {
+        return None;
+    }
+
+    let start = length;
+    while let Some(space) = text[length..]
+        .chars()
+        .next()
+        .filter(|c| GROUP_SPACES.contains(c))
+    {
+        let group = length + space.len_utf8();
+        if digits(&text[group..]) != 3 {
+            break;
+        }
+        length = group + 3;
+    }
+
+    (length > start).then_some(length)
+}
+
+/// Removes the spaces between the groups of thousands of a number, as in
+/// "1 000 000".
+fn join_groups(text: &str) -> Cow<'_, str> {
+    match spaced_length(text) {
+        Some(length) => {
+            let mut joined = text[..length].replace(GROUP_SPACES, "");
+            joined.push_str(&text[length..]);
+            Cow::Owned(joined)
+        }
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Splits a number into its integer digits and its decimals.
+///
+/// A `.` or `,` followed by exactly three digits separates groups of
+/// thousands if the language uses it so, as "," in English "1,000" or "."
+/// in French "1.000", and decimals otherwise. Repeated, it always separates
+/// groups of thousands, as in "1,000,000", and a last different one
+/// separates decimals, as in "1.000,5". Returns [`None`] if the groups of
+/// thousands are not of three digits, as in "1,5,6".
+fn split_decimal<'a>(text: &'a str, lang: &str) -> Option<(String, &'a str)> {
+    let parts: Vec<&str> = text.split(['.', ',']).collect();
+    if !parts
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let grouping = if DECIMAL_COMMA.contains(&lang) {
+        '.'
+    } else {
+        ','
+    };
+    let separators: Vec<char> = text.chars().filter(|c| matches!(c, '.' | ',')).collect();
+    let (groups, decimals) = match separators.as_slice() {
+        [] => (&parts[..], ""),
+        [separator] if *separator == grouping && parts[1].len() == 3 => (&parts[..], ""),
+        [_] => (&parts[..1], parts[1]),
+        [first, .., last]
+            if separators[..separators.len() - 1]
+                .iter()
+                .all(|c| c == first) =>
+        {
+            if last == first {
+                (&parts[..], "")
+            } else {
+                (&parts[..parts.len() - 1], parts[parts.len() - 1])
+            }
+        }
+        _ => return None,
+    };
+
+    let thousands = groups.len() == 1
+        || (groups[0].len() <= 3 && groups[1..].iter().all(|group| group.len() == 3));
+    thousands.then(|| (groups.concat(), decimals))
+}
+
+/// Writes a number without separators of thousands, and with a `.` before
+/// its decimals, so "1,000,000" and "1.000.000" give "1000000".
+fn decimal(text: &str, lang: &str) -> Option<String> {
+    let (integer, decimals) = split_decimal(text, lang)?;
+
+    Some(if decimals.is_empty() {
+        integer
+    } else {
+        format!("{}.{}", integer, decimals)
+    })
+}
+
+/// Multiplies a number, rounded to the unit, with integers so large
+/// quantities stay exact. Returns [`None`] on overflow.
+fn quantity(value: &str, multiplier: u128, lang: &str) -> Option<String> {
+    let (integer, decimals) = split_decimal(value, lang)?;
+    let scale = 10u128.checked_pow(decimals.len() as u32)?;
+    let digits: u128 = format!("{}{}", integer, decimals).parse().ok()?;
+
+    let quantity = digits.checked_mul(multiplier)?.checked_add(scale / 2)? / scale;
+    Some(quantity.to_string())
+}
+
+/// Returns the full name of a month, in the language of the name.
+fn month(name: &str, lang: &str) -> Option<&'static str> {
+    let name = normalize::fold_accents(name);
+    let mut languages = MONTHS
+        .iter()
+        .filter(|(code, _)| *code == lang)
+        .chain(MONTHS.iter().filter(|(code, _)| *code != lang));
+
+    languages.find_map(|(_, months)| {
+        months
+            .iter()
+            .find(|names| names.contains(&name.as_ref()))
+            .map(|names| names[0])
+    })
+}
+
+/// Normalizes a score, such as "3-1", without leading zeros.
+fn score(text: &str) -> Option<String> {
+    let (home, away) = text.split_once('-')?;
+    let goals = |part: &str| {
+        (1..=2).contains(&part.len()) && part.bytes().all(|byte| byte.is_ascii_digit())
+    };
+
+    (goals(home) && goals(away)).then(|| {
+        format!(
+            "{}-{}",
+            home.parse::<u8>().unwrap_or_default(),
+            away.parse::<u8>().unwrap_or_default()
+        )
+    })
+}
+
+/// Normalizes a date written with digits, such as "08/03/2024" or
+/// "2024-03-08", without leading zeros. Dashes are only read in years first,
+/// since "3-1" is usually a score.
+fn numeric_date(text: &str) -> Option<String> {
+    let separator = if text.contains('/') { '/' } else { '-' };
+    let parts: Vec<&str> = text.split(separator).collect();
+    if !(2..=3).contains(&parts.len())
+        || !parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let valid = match (separator, parts[0].len(), numbers.as_slice()) {
+        // Year first.
+        ('-', 4, [_, month, day]) => (1..=12).contains(month) && (1..=31).contains(day),
+        ('/', 1..=2, [day, month, ..]) => {
+            (1..=31).contains(day)
+                && (1..=12).contains(month)
+                && parts.get(2).is_none_or(|year| matches!(year.len(), 2 | 4))
+        }
+        _ => false,
+    };
+
+    valid.then(|| {
+        numbers
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(&separator.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(text: &str) -> Option<(TokenKind, String)> {
+        parse(text, "fr").map(|numeral| (numeral.kind, numeral.normalized))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            normalized("14juillet"),
+            Some((TokenKind::Date, "14 juillet".to_string()))
+        );
+        assert_eq!(
+            normalized("25DÉC"),
+            Some((TokenKind::Date, "25 decembre".to_string()))
+        );
+        assert_eq!(
+            normalized("08/03/2024"),
+            Some((TokenKind::Date, "8/3/2024".to_string()))
+        );
+        assert_eq!(
+            normalized("2024-03-08"),
+            Some((TokenKind::Date, "2024-3-8".to_string()))
+        );
+        assert_eq!(
+            normalized("2ème"),
+            Some((TokenKind::Number, "2".to_string()))
+        );
+        assert_eq!(
+            normalized("3rd"),
+            Some((TokenKind::Number, "3".to_string()))
+        );
+        assert_eq!(
+            normalized("10K"),
+            Some((TokenKind::Number, "10000".to_string()))
+        );
+        assert_eq!(
+            normalized("50%"),
+            Some((TokenKind::Number, "50%".to_string()))
+        );
+        assert_eq!(
+            normalized("$5"),
+            Some((TokenKind::Number, "$5".to_string()))
+        );
+        assert_eq!(normalized("32mars"), None);
+        assert_eq!(
+            normalized("8 mars"),
+            Some((TokenKind::Date, "8 mars".to_string()))
+        );
+        assert_eq!(normalized("13/13"), None);
+        assert_eq!(
+            normalized("03-1"),
+            Some((TokenKind::Number, "3-1".to_string()))
+        );
+        assert_eq!(normalized("2024-03"), None);
+        assert_eq!(normalized("1..2"), None);
+        assert_eq!(normalized("3d"), None);
+        assert_eq!(normalized("100m"), None);
+        assert_eq!(normalized("5m"), None);
+        assert_eq!(
+            normalized("00th"),
+            Some((TokenKind::Number, "0".to_string()))
+        );
+        assert_eq!(normalized("1,5,6k"), None);
+        assert_eq!(normalized("1,5,6"), None);
+        for text in ["1.000.000", "1,000,000", "1000000"] {
+            assert_eq!(
+                normalized(text),
+                Some((TokenKind::Number, "1000000".to_string()))
+            );
+        }
+        for (text, lang, number) in [
+            ("1,000", "en", "1000"),
+            ("1,000", "fr", "1.000"),
+            ("1.000", "fr", "1000"),
+            ("1.000", "en", "1.000"),
+            ("1,5", "fr", "1.5"),
+            ("1,5", "en", "1.5"),
+            ("1 000", "fr", "1000"),
+            ("1\u{A0}000,5€", "fr", "1000.5€"),
+        ] {
+            assert_eq!(parse(text, lang).unwrap().normalized, number, "{}", text);
+        }
+        assert_eq!(
+            normalized("1.000,5%"),
+            Some((TokenKind::Number, "1000.5%".to_string()))
+        );
+        assert_eq!(
+            normalized("$1,000,000"),
+            Some((TokenKind::Number, "$1000000".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_quantities() {
+        let quantity = |text| normalized(text).map(|(_, normalized)| normalized);
+
+        assert_eq!(quantity("1,5k").as_deref(), Some("1500"));
+        assert_eq!(quantity("2.25k").as_deref(), Some("2250"));
+        assert_eq!(quantity("1,2345k").as_deref(), Some("1235"));
+        assert_eq!(quantity("1,000k").as_deref(), Some("1000"));
+        assert_eq!(quantity("1,000,000k").as_deref(), Some("1000000000"));
+        assert_eq!(quantity("1.000,5k").as_deref(), Some("1000500"));
+        assert_eq!(quantity("3bn").as_deref(), Some("3000000000"));
+        assert_eq!(
+            quantity("99999999999999999999999k").as_deref(),
+            Some("99999999999999999999999000")
+        );
+        // Overflows.
+        let large = format!("{}k", "9".repeat(40));
+        assert_eq!(quantity(&large), Some(large.clone()));
+        assert_eq!(quantity("1,23,456k"), None);
+    }
+
+    #[test]
+    fn test_normalized_kind() {
+        // Stored keys must be ranked under the kind they were parsed as.
+        for text in [
+            "14juillet",
+            "25DÉC",
+            "08/03/2024",
+            "2024-03-08",
+            "8/3",
+            "2ème",
+            "0e",
+            "10K",
+            "1,5k",
+            "3bn",
+            "50%",
+            "$5",
+            "1,5",
+            "1,000,000",
+            "3-1",
+            "2024",
+        ] {
+            let (kind, normalized) = normalized(text).unwrap();
+            assert_eq!(TokenKind::of(&normalized), kind, "{}", normalized);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! extraction of proper-noun phrases.
+//!
+//! Names such as "Aya Nakamura" or "Megan Thee Stallion" are recognized from
+//! the capitalization of the words read by the tokenizer: a phrase is a run of
+//! capitalized words only separated by spaces. The first word of a sentence
+//! is capitalized anyway, so it is left out of a phrase unless it is also
+//! capitalized in the middle of a sentence of the text, and capitalized stop
+//! words such as "The" or "Les" never belong to a phrase.
+
+use crate::{elision, stopwords::StopWords, Token, TokenKind};
+use std::borrow::Cow;
+
+/// Maximum number of words of a phrase. Longer runs are usually titles
+/// written in title case, and are ignored.
+const MAX_WORDS: usize = 4;
+
+/// Characters ending a sentence.
+const SENTENCE_ENDS: [char; 4] = ['.', '!', '?', '…'];
+
+/// Proper-noun phrases being read from the tokens of a text.
+///
+/// Tokens are given in the order of the text, and a phrase is returned once
+/// the next token ends it. The text of a phrase is made of its words as
+/// written, separated by a space, and [`Tokens`](crate::Tokens) turns it into
+/// the key of the phrase.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::{tokens, TokenKind};
+///
+/// let text = "Concert de Megan Thee Stallion et d'Aya Nakamura. Merci Simone Veil";
+/// let phrases: Vec<_> = tokens(text)
+///     .lang("fr")
+///     .phrases(true)
+///     .filter(|token| token.kind == TokenKind::Phrase)
+///     .map(|token| token.text)
+///     .collect();
+///
+/// assert_eq!(
+///     phrases,
+///     vec!["megan thee stallion", "aya nakamura", "simone veil"]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Phrases<'a> {
+    /// Capitalized words read since the last phrase ended.
+    run: Vec<Token<'a>>,
+    /// Whether the first word of the run starts a sentence.
+    sentence_start: bool,
+    /// End of the previous token, in bytes.
+    end: usize,
+}
+
+impl<'a> Phrases<'a> {
+    /// Reads the next token of a text, returning the phrase it ends, if any.
+    ///
+    /// Stop words are expected in lowercase.
+    pub fn push(
+        &mut self,
+        text: &'a str,
+        token: &Token<'a>,
+        stopwords: &StopWords,
+    ) -> Option<Token<'a>> {
+        // Text between tokens, such as punctuation and removed words.
+        let gap = text
+            .get(self.end..token.byte_range.start)
+            .unwrap_or_default();
+        let sentence_start =
+            starts_sentence(text.get(..token.byte_range.start).unwrap_or_default());
+        self.end = token.byte_range.end;
+
+        let phrase = if sentence_start || !gap.chars().all(char::is_whitespace) {
+            self.close(text)
+        } else {
+            None
+        };
+
+        // A sentence start closes the run, so its first word is only left
+        // out when the run is closed.
+        if token.kind == TokenKind::Word && is_name(&token.display, stopwords) {
+            if self.run.is_empty() {
+                self.sentence_start = sentence_start;
+            }
+            self.run.push(token.clone());
+            phrase
+        } else {
+            phrase.or_else(|| self.close(text))
+        }
+    }
+
+    /// Returns the phrase ending the text, if any.
+    pub fn finish(&mut self, text: &'a str) -> Option<Token<'a>> {
+        self.close(text)
+    }
+
+    /// Returns the phrase made of the current run of words, if it is one.
+    fn close(&mut self, text: &'a str) -> Option<Token<'a>> {
+        if self.sentence_start
+            && self
+                .run
+                .first()
+                .is_some_and(|first| !is_name_in_sentence(text, &first.display))
+        {
+            self.run.remove(0);
+        }
+        self.sentence_start = false;
+
+        let phrase = match self.run.as_slice() {
+            [first, .., last]
+                if self.run.len() <= MAX_WORDS
+                    && !self.run.iter().all(|word| is_shouted(&word.display)) =>
+            {
+                let byte_range = first.byte_range.start..last.byte_range.end;
+
+                Some(Token {
+                    text: Cow::Owned(
+                        self.run
+                            .iter()
+                            .map(|word| word.display.as_ref())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ),
+                    display: Cow::Borrowed(&text[byte_range.clone()]),
+                    kind: TokenKind::Phrase,
+                    byte_range,
+                    flagged: self.run.iter().any(|word| word.flagged),
+                })
+            }
+            _ => None,
+        };
+
+        self.run.clear();
+        phrase
+    }
+}
+
+/// Whether a word, as written, may be part of a name.
+fn is_name(word: &str, stopwords: &StopWords) -> bool {
+    word.starts_with(char::is_uppercase)
+        && word
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || elision::is_apostrophe(c))
+        && !stopwords.contains(&word.to_lowercase())
+}
+
+/// Whether a text up to a word ends with the end of a sentence, or is empty.
+fn starts_sentence(before: &str) -> bool {
+    // Without the start of the chunk of the word, such as "d'" in "d'Aya".
+    let before = before.trim_end_matches(|c: char| !c.is_whitespace());
+    let spaces = &before[before.trim_end().len()..];
+
+    spaces.contains('\n')
+        || before
+            .split_whitespace()
+            .next_back()
+            .is_none_or(|chunk| chunk.ends_with(SENTENCE_ENDS))
+}
+
+/// Whether a word, as written, is found in the middle of a sentence of a
+/// text.
+fn is_name_in_sentence(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, _)| {
+        let (before, after) = (&text[..start], &text[start + word.len()..]);
+
+        !before.ends_with(char::is_alphanumeric)
+            && !after.starts_with(char::is_alphanumeric)
+            && !starts_sentence(before)
+    })
+}
+
+/// Whether a word is written in uppercase only, as in shouted texts.
+fn is_shouted(word: &str) -> bool {
+    !word.chars().any(char::is_lowercase)
+}
+
+// Built-in French stop words, such as "les", are not part of phrases.
+#[cfg(all(test, feature = "fr"))]
+mod tests {
+    use crate::{tokens, TokenKind};
+
+    fn find(text: &str) -> Vec<String> {
+        tokens(text)
+            .lang("fr")
+            .phrases(true)
+            .filter(|token| token.kind == TokenKind::Phrase)
+            .map(|token| token.display.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_phrases() {
+        assert_eq!(
+            find("Hommage à Simone Veil, « Jean-Luc Mélenchon » et Les Bleus"),
+            vec!["Simone Veil", "Jean-Luc Mélenchon"]
+        );
+        assert_eq!(find("Aya Nakamura chante ce soir"), Vec::<String>::new());
+        assert_eq!(
+            find("Aya Nakamura chante ce soir, bravo Aya"),
+            vec!["Aya Nakamura"]
+        );
+        assert_eq!(
+            find("Hier soir, Aya Nakamura a chanté"),
+            vec!["Aya Nakamura"]
+        );
+        assert_eq!(
+            find("Vu hier\nParis Saint-Germain gagne, allez Paris"),
+            vec!["Paris Saint-Germain"]
+        );
+        assert_eq!(find("Il pleut. Paris gagne"), Vec::<String>::new());
+        assert_eq!(find("je suis EN COLERE contre"), Vec::<String>::new());
+        assert_eq!(find("avec le PSG Handball"), vec!["PSG Handball"]);
+        assert_eq!(
+            find("livre Petit Prince Étoiles Filantes Magiques"),
+            Vec::<String>::new()
+        );
+        assert_eq!(find("vu Kylian Mbappé"), vec!["Kylian Mbappé"]);
+    }
+
+    #[test]
+    fn test_sentence_start() {
+        assert_eq!(find("Merci Emmanuel Macron"), vec!["Emmanuel Macron"]);
+        assert_eq!(find("Bonjour Paris"), Vec::<String>::new());
+        assert_eq!(find("Hier Aya Nakamura chante"), vec!["Aya Nakamura"]);
+        assert_eq!(find("Il pleut. Merci Simone Veil"), vec!["Simone Veil"]);
+    }
+
+    #[test]
+    fn test_phrase_keys() {
+        let keys: Vec<_> = tokens("Hommage à Simone Veil et Megan Thee Stallion")
+            .lang("fr")
+            .phrases(true)
+            .lemmatize(true)
+            .stem(true)
+            .filter(|token| token.kind == TokenKind::Phrase)
+            .map(|token| token.text.into_owned())
+            .collect();
+
+        assert_eq!(keys, vec!["simone veil", "megan thee stallion"]);
+    }
+}
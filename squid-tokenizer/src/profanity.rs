@@ -0,0 +1,144 @@
+//! flagging of abusive terms.
+//!
+//! Lexicons are plain text files, one term per line, by language. Lines
+//! starting with `#` are ignored. Terms and words are compared after
+//! normalization, so "M3RDE", "merdeeee" and "merde" all match the same
+//! term, but "as" does not match "ass". Flagged words are annotated, not
+//! removed.
+
+use crate::{normalize, slang};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+static LEXICON: OnceLock<Lexicon> = OnceLock::new();
+
+/// Abusive terms, by language.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::profanity::lexicon;
+///
+/// lexicon().extend("fr", ["merde"]);
+///
+/// assert!(lexicon().matches("M3RDEEE", "fr"));
+/// assert!(lexicon().matches("merde", ""));
+/// assert!(!lexicon().matches("merde", "en"));
+/// ```
+#[derive(Debug, Default)]
+pub struct Lexicon {
+    terms: RwLock<HashMap<String, Arc<HashSet<String>>>>,
+}
+
+impl Lexicon {
+    /// Reads the terms of a language, identified by its ISO 639-1 code
+    /// (e.g. `fr`).
+    ///
+    /// Replaces the previous terms of the language. Returns the number of
+    /// terms loaded.
+    pub fn load<P: AsRef<Path>>(&self, lang: &str, path: P) -> io::Result<usize> {
+        let mut terms = HashSet::new();
+
+        for line in BufReader::new(OpenOptions::new().read(true).open(path)?).lines() {
+            let line = line?;
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                terms.insert(key(line));
+            }
+        }
+
+        let length = terms.len();
+        self.terms
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(lang.to_string(), Arc::new(terms));
+
+        Ok(length)
+    }
+
+    /// Adds terms to a language.
+    pub fn extend<I, T>(&self, lang: &str, terms: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut lexicons = self.terms.write().unwrap_or_else(PoisonError::into_inner);
+        let lexicon = lexicons.entry(lang.to_string()).or_default();
+
+        Arc::make_mut(lexicon).extend(terms.into_iter().map(|term| key(term.as_ref())));
+    }
+
+    /// Whether a word is an abusive term of a language, or of any language
+    /// if it is empty.
+    pub fn matches(&self, word: &str, lang: &str) -> bool {
+        let terms = self.terms.read().unwrap_or_else(PoisonError::into_inner);
+        if terms.is_empty() {
+            return false;
+        }
+
+        let known = |key: &str| {
+            if lang.is_empty() {
+                terms.values().any(|terms| terms.contains(key))
+            } else {
+                terms.get(lang).is_some_and(|terms| terms.contains(key))
+            }
+        };
+
+        let key = key(word);
+        known(&slang::collapse_repeats_with(&key, known))
+    }
+}
+
+/// Returns the global [`Lexicon`].
+pub fn lexicon() -> &'static Lexicon {
+    LEXICON.get_or_init(Lexicon::default)
+}
+
+/// Normalizes a word to compare it with terms, whatever the settings of the
+/// tokenizer.
+///
+/// Runs of three or more letters are collapsed when compared, with
+/// [`slang::collapse_repeats_with`], so "connaaard" matches "connard" while
+/// doubled letters are kept.
+fn key(word: &str) -> String {
+    let word = normalize::normalize(word.trim_start_matches('#'));
+    let word = normalize::fold_accents(&word);
+    slang::fold_leet(&word).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join("squid_test_profanity_load.txt");
+        std::fs::write(&path, "# insults\nconnard\nputain\n\n").unwrap();
+
+        let lexicon = Lexicon::default();
+        assert!(!lexicon.matches("connard", "fr"));
+        assert_eq!(lexicon.load("fr", &path).unwrap(), 2);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(lexicon.matches("#CONNAAARD", "fr"));
+        assert!(lexicon.matches("put4in", "fr"));
+        assert!(!lexicon.matches("connaissance", "fr"));
+    }
+
+    #[test]
+    fn test_doubled_letters() {
+        let lexicon = Lexicon::default();
+        lexicon.extend("en", ["ass", "butt"]);
+
+        assert!(lexicon.matches("ass", "en"));
+        assert!(lexicon.matches("asssss", "en"));
+        assert!(lexicon.matches("BUTTTT", ""));
+        assert!(!lexicon.matches("as", "en"));
+        assert!(!lexicon.matches("but", "en"));
+        assert!(!lexicon.matches("but", ""));
+    }
+}
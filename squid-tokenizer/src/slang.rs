@@ -0,0 +1,194 @@
+//! normalization of informal spellings.
+//!
+//! Elongated words such as "soooo" or "loooool" and leetspeak such as
+//! "gr8" or "m4cron" are folded to their usual spelling, so variants share
+//! the same key and do not evade exclusion lists.
+
+use std::borrow::Cow;
+
+/// Minimum length of a run of a letter to collapse it.
+const MIN_RUN: usize = 3;
+/// Length of a collapsed run of a word which is not known. Two, not one,
+/// since many words double letters, as "cool" or "Schifffahrt".
+const COLLAPSED_RUN: usize = 2;
+
+/// Informal words often elongated, known without a lexicon.
+const KNOWN: [&str; 12] = [
+    "lol", "mdr", "ptdr", "omg", "wtf", "lmao", "oh", "ah", "eh", "yes", "oui", "non",
+];
+
+/// Leetspeak words which are not spelled out letter by letter.
+const WORDS: [(&str, &str); 4] = [
+    ("gr8", "great"),
+    ("l8", "late"),
+    ("str8", "straight"),
+    ("w8", "wait"),
+];
+
+/// Letters written with digits or symbols.
+const LEET: [(char, &str); 9] = [
+    ('0', "o"),
+    ('1', "i"),
+    ('3', "e"),
+    ('4', "a"),
+    ('5', "s"),
+    ('7', "t"),
+    ('8', "ate"),
+    ('@', "a"),
+    ('$', "s"),
+];
+
+/// Collapses runs of three or more identical letters into two, or into one
+/// for a few informal words such as "lol".
+///
+/// Digits are never collapsed, so "2000" stays unchanged, and neither are
+/// words made of a single letter, such as "www". See
+/// [`collapse_repeats_with`] to collapse into one letter the words of a
+/// lexicon.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::slang::collapse_repeats;
+///
+/// assert_eq!(collapse_repeats("loooool"), "lol");
+/// assert_eq!(collapse_repeats("sooooo"), "soo");
+/// assert_eq!(collapse_repeats("cool"), "cool");
+/// ```
+pub fn collapse_repeats(word: &str) -> Cow<'_, str> {
+    collapse_repeats_with(word, |_| false)
+}
+
+/// Collapses runs of three or more identical letters into one if the word
+/// is then known, from the informal words of [`collapse_repeats`] or from a
+/// lexicon, and into two otherwise.
+///
+/// The lexicon is given words without the `#` of hashtags.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::slang::collapse_repeats_with;
+///
+/// assert_eq!(collapse_repeats_with("sooooo", |word| word == "so"), "so");
+/// assert_eq!(collapse_repeats_with("cooool", |word| word == "so"), "cool");
+/// ```
+pub fn collapse_repeats_with(word: &str, known: impl Fn(&str) -> bool) -> Cow<'_, str> {
+    let chars: Vec<char> = word.chars().collect();
+    let elongated = chars
+        .windows(MIN_RUN)
+        .any(|run| run[0].is_alphabetic() && run.iter().all(|c| *c == run[0]));
+
+    if !elongated || chars.iter().all(|c| *c == chars[0]) {
+        return Cow::Borrowed(word);
+    }
+
+    let once = collapse(&chars, 1);
+    let body = once.trim_start_matches('#');
+    if KNOWN.contains(&body) || known(body) {
+        return Cow::Owned(once);
+    }
+
+    Cow::Owned(collapse(&chars, COLLAPSED_RUN))
+}
+
+/// Replaces runs of three or more identical letters by a shorter run.
+fn collapse(chars: &[char], run: usize) -> String {
+    let mut collapsed = String::with_capacity(chars.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        let length = chars[index..].iter().take_while(|next| **next == c).count();
+
+        if c.is_alphabetic() && length >= MIN_RUN {
+            collapsed.extend(&chars[index..index + run]);
+        } else {
+            collapsed.extend(&chars[index..index + length]);
+        }
+        index += length;
+    }
+
+    collapsed
+}
+
+/// Replaces digits and symbols used as letters.
+///
+/// A character is only replaced between two letters, or at the end of a
+/// word for `8` ("gr8"). Words keeping other digits, such as "covid19" or
+/// "mp3", are left unchanged.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::slang::fold_leet;
+///
+/// assert_eq!(fold_leet("m4cron"), "macron");
+/// assert_eq!(fold_leet("gr8"), "great");
+/// assert_eq!(fold_leet("2024"), "2024");
+/// ```
+pub fn fold_leet(word: &str) -> Cow<'_, str> {
+    if let Some((_, folded)) = WORDS.iter().find(|(leet, _)| *leet == word) {
+        return Cow::Borrowed(folded);
+    }
+
+    if word.chars().all(char::is_alphabetic) || !word.chars().any(char::is_alphabetic) {
+        return Cow::Borrowed(word);
+    }
+
+    let chars: Vec<char> = word.chars().collect();
+    let mut folded = String::with_capacity(word.len() + 2);
+
+    for (index, c) in chars.iter().enumerate() {
+        if c.is_alphabetic() {
+            folded.push(*c);
+            continue;
+        }
+
+        let after_letter = index > 0 && chars[index - 1].is_alphabetic();
+        let before_letter = chars
+            .get(index + 1)
+            .is_some_and(|next| next.is_alphabetic());
+        let at_end = index + 1 == chars.len();
+
+        match LEET.iter().find(|(leet, _)| leet == c) {
+            Some((leet, letters))
+                if after_letter && (before_letter || (*leet == '8' && at_end)) =>
+            {
+                folded.push_str(letters)
+            }
+            // Not leetspeak.
+            _ if c.is_alphanumeric() || LEET.iter().any(|(leet, _)| leet == c) => {
+                return Cow::Borrowed(word)
+            }
+            _ => folded.push(*c),
+        }
+    }
+
+    Cow::Owned(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_repeats() {
+        assert_eq!(collapse_repeats("soooo"), "soo");
+        assert_eq!(collapse_repeats("#jooooo"), "#joo");
+        assert_eq!(collapse_repeats("#looool"), "#lol");
+        assert_eq!(collapse_repeats("2000"), "2000");
+        assert_eq!(collapse_repeats("zzzz"), "zzzz");
+        assert_eq!(collapse_repeats("www"), "www");
+        assert_eq!(collapse_repeats("schifffahrt"), "schiffahrt");
+        assert_eq!(collapse_repeats("kaffee"), "kaffee");
+    }
+
+    #[test]
+    fn test_fold_leet() {
+        assert_eq!(fold_leet("h4x0r"), "haxor");
+        assert_eq!(fold_leet("l8r"), "later");
+        assert_eq!(fold_leet("ca$h"), "cash");
+        assert_eq!(fold_leet("#m4cron"), "#macron");
+        assert_eq!(fold_leet("covid19"), "covid19");
+        assert_eq!(fold_leet("mp3"), "mp3");
+        assert_eq!(fold_leet("8mars"), "8mars");
+    }
+}
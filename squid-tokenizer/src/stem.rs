@@ -0,0 +1,74 @@
+//! snowball stemming of words.
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::{borrow::Cow, collections::HashMap, sync::OnceLock};
+
+/// Snowball algorithm of each language, by ISO 639-1 code.
+const ALGORITHMS: [(&str, Algorithm); 18] = [
+    ("ar", Algorithm::Arabic),
+    ("da", Algorithm::Danish),
+    ("de", Algorithm::German),
+    ("el", Algorithm::Greek),
+    ("en", Algorithm::English),
+    ("es", Algorithm::Spanish),
+    ("fi", Algorithm::Finnish),
+    ("fr", Algorithm::French),
+    ("hu", Algorithm::Hungarian),
+    ("it", Algorithm::Italian),
+    ("nl", Algorithm::Dutch),
+    ("nb", Algorithm::Norwegian),
+    ("pt", Algorithm::Portuguese),
+    ("ro", Algorithm::Romanian),
+    ("ru", Algorithm::Russian),
+    ("sv", Algorithm::Swedish),
+    ("ta", Algorithm::Tamil),
+    ("tr", Algorithm::Turkish),
+];
+
+static STEMMERS: OnceLock<HashMap<&'static str, Stemmer>> = OnceLock::new();
+
+/// Returns the stemmer of a language, created once for every word.
+fn stemmer(lang: &str) -> Option<&'static Stemmer> {
+    STEMMERS
+        .get_or_init(|| {
+            ALGORITHMS
+                .into_iter()
+                .map(|(lang, algorithm)| (lang, Stemmer::create(algorithm)))
+                .collect()
+        })
+        .get(lang)
+}
+
+/// Reduces a lowercase word to its stem.
+///
+/// Words of languages without Snowball stemmer are returned unchanged.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::stem::stem;
+///
+/// assert_eq!(stem("manifestations", "fr"), stem("manifestant", "fr"));
+/// assert_eq!(stem("running", "en"), "run");
+/// assert_eq!(stem("running", "xx"), "running");
+/// ```
+pub fn stem<'a>(word: &'a str, lang: &str) -> Cow<'a, str> {
+    match stemmer(lang) {
+        Some(stemmer) => stemmer.stem(word),
+        None => Cow::Borrowed(word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stem() {
+        assert_eq!(stem("manifestation", "fr"), "manifest");
+        assert_eq!(stem("manifestations", "fr"), "manifest");
+        assert_eq!(stem("manifestant", "fr"), "manifest");
+        assert_eq!(stem("connections", "en"), "connect");
+        // Code of Norwegian Bokmål, as detected.
+        assert_eq!(stem("bilene", "nb"), "bil");
+    }
+}
@@ -1,31 +1,250 @@
 //! filters unnecessary words and removes it from sentences.
+//!
+//! Curated lists are compiled into the crate for each enabled language
+//! feature (`fr`, `en`, `es`, `de`, `it`). Custom words of each service are
+//! layered on top of them, whatever the language, and can be reloaded at
+//! runtime through the [`Registry`].
 
 use std::{
+    collections::{HashMap, HashSet},
     fs::OpenOptions,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Write},
     path::PathBuf,
-    sync::OnceLock,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock, PoisonError, RwLock,
+    },
 };
 
-static STOP_WORDS: OnceLock<Vec<String>> = OnceLock::new();
+/// Hashed set of stop words.
+pub type StopWords = HashSet<String>;
 
-/// Inits `STOP_WORDS` by adding every lines from a text file
-/// to the cache.
-pub fn init(path: PathBuf) {
-    STOP_WORDS.get_or_init(|| {
-        if let Ok(file) = OpenOptions::new().read(true).open(path) {
-            let reader = BufReader::new(&file);
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+static BUILTIN_STOP_WORDS: OnceLock<HashMap<&'static str, HashSet<&'static str>>> = OnceLock::new();
 
-            let mut words: Vec<String> = vec![];
-            for word in reader.lines().map_while(Result::ok) {
-                words.push(word)
+/// Stop words of every service, by language.
+///
+/// Sets are shared behind an [`Arc`]: reloading a service swaps its sets
+/// while texts being tokenized keep using the previous ones.
+///
+/// # Example
+/// ```no_run,rust
+/// use squid_tokenizer::stopwords::registry;
+/// use std::path::PathBuf;
+///
+/// registry()
+///     .register("gravitalia", PathBuf::from("./stopwords"))
+///     .unwrap();
+///
+/// // ... after editing `./stopwords`.
+/// registry().reload("gravitalia").unwrap();
+/// assert!(registry().get("gravitalia", "fr").contains("les"));
+/// ```
+#[derive(Debug, Default)]
+pub struct Registry {
+    /// Custom stop words file of each service.
+    sources: RwLock<HashMap<String, PathBuf>>,
+    /// Custom stop words of each service, read from its source.
+    custom: RwLock<HashMap<String, Arc<StopWords>>>,
+    /// Built-in and custom stop words merged, by service and language.
+    sets: RwLock<HashMap<String, HashMap<String, Arc<StopWords>>>>,
+    /// Number of reloads, to detect sets built from outdated custom words.
+    generation: AtomicU64,
+}
+
+impl Registry {
+    /// Sets the custom stop words file of a service and loads it.
+    ///
+    /// The file contains one word per line.
+    pub fn register(&self, service: &str, path: PathBuf) -> io::Result<()> {
+        self.sources
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(service.to_string(), path);
+
+        self.reload(service)
+    }
+
+    /// Whether a custom stop words file is set for a service.
+    pub fn is_registered(&self, service: &str) -> bool {
+        self.sources
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(service)
+    }
+
+    /// Reads again the custom stop words file of a service.
+    ///
+    /// If the file cannot be read, previous stop words are kept.
+    pub fn reload(&self, service: &str) -> io::Result<()> {
+        let path = self
+            .sources
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(service)
+            .cloned();
+
+        let words: StopWords = match path {
+            Some(path) => BufReader::new(OpenOptions::new().read(true).open(path)?)
+                .lines()
+                .collect::<io::Result<Vec<_>>>()?
+                .into_iter()
+                .map(|word| word.trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+            None => StopWords::default(),
+        };
+
+        self.custom
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(service.to_string(), Arc::new(words));
+
+        // Rebuild merged sets before swapping them.
+        let mut sets = self.sets.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(langs) = sets.get_mut(service) {
+            for (lang, set) in langs.iter_mut() {
+                *set = self.build(service, lang);
             }
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        Ok(())
+    }
+
+    /// Appends words to the custom stop words file of a service, then
+    /// reloads it.
+    ///
+    /// Returns an error if no file is set for the service.
+    pub fn append<I, T>(&self, service: &str, words: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let path = self
+            .sources
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(service)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no stop words file"))?;
+
+        // Do not glue the first word to a last line without line break.
+        let newline =
+            std::fs::read(&path).is_ok_and(|bytes| bytes.last().is_some_and(|byte| *byte != b'\n'));
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if newline {
+            writeln!(file)?;
+        }
+        for word in words {
+            writeln!(file, "{}", word.as_ref().trim().to_lowercase())?;
+        }
+
+        self.reload(service)
+    }
+
+    /// Returns stop words of a service for a language.
+    ///
+    /// Looking up already merged sets does not allocate. Languages without
+    /// built-in list share the set of custom words, so unknown codes do not
+    /// each keep a set.
+    pub fn get(&self, service: &str, lang: &str) -> Arc<StopWords> {
+        let lang = if builtin(lang).is_some() { lang } else { "" };
+
+        if let Some(set) = self
+            .sets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(service)
+            .and_then(|langs| langs.get(lang))
+        {
+            return Arc::clone(set);
+        }
+
+        // Built outside the lock, then checked against reloads under it.
+        let generation = self.generation.load(Ordering::Acquire);
+        let set = self.build(service, lang);
+
+        let mut sets = self.sets.write().unwrap_or_else(PoisonError::into_inner);
+        let langs = sets.entry(service.to_string()).or_default();
+        if let Some(set) = langs.get(lang) {
+            return Arc::clone(set);
+        }
 
-            words
+        let set = if self.generation.load(Ordering::Acquire) == generation {
+            set
         } else {
-            Vec::default()
+            self.build(service, lang)
+        };
+        langs.insert(lang.to_string(), Arc::clone(&set));
+        set
+    }
+
+    /// Merges built-in stop words of a language with custom ones of a service.
+    fn build(&self, service: &str, lang: &str) -> Arc<StopWords> {
+        let mut set: StopWords = builtin(lang)
+            .map(|list| list.iter().map(|word| word.to_string()).collect())
+            .unwrap_or_default();
+
+        if let Some(custom) = self
+            .custom
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(service)
+        {
+            set.extend(custom.iter().cloned());
         }
-    });
+
+        Arc::new(set)
+    }
+}
+
+/// Returns the global stop words [`Registry`].
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::default)
+}
+
+/// Loads custom stop words of the default service (`""`) from a text file,
+/// with one word per line.
+///
+/// Does nothing if they are already loaded. Returns an error if the file
+/// cannot be read; only built-in lists are then used.
+pub fn init(path: PathBuf) -> io::Result<()> {
+    if registry().is_registered("") {
+        return Ok(());
+    }
+
+    registry().register("", path)
+}
+
+/// Returns the curated stop words of a language, identified by its
+/// ISO 639-1 code (e.g. `fr`).
+///
+/// Returns [`None`] if the language is unknown or its feature is disabled.
+pub fn builtin(lang: &str) -> Option<&'static HashSet<&'static str>> {
+    BUILTIN_STOP_WORDS
+        .get_or_init(|| {
+            #[allow(unused_mut)]
+            let mut lists: HashMap<&str, &str> = HashMap::new();
+
+            #[cfg(feature = "de")]
+            lists.insert("de", include_str!("../data/stopwords/de.txt"));
+            #[cfg(feature = "en")]
+            lists.insert("en", include_str!("../data/stopwords/en.txt"));
+            #[cfg(feature = "es")]
+            lists.insert("es", include_str!("../data/stopwords/es.txt"));
+            #[cfg(feature = "fr")]
+            lists.insert("fr", include_str!("../data/stopwords/fr.txt"));
+            #[cfg(feature = "it")]
+            lists.insert("it", include_str!("../data/stopwords/it.txt"));
+
+            lists
+                .into_iter()
+                .map(|(lang, list)| (lang, list.lines().collect()))
+                .collect()
+        })
+        .get(lang)
 }
 
 /// Removes every stop words from a sentence.
@@ -35,27 +254,66 @@ pub fn init(path: PathBuf) {
 /// use std::{fs::File, io::prelude::*, path::Path};
 /// use squid_tokenizer::stopwords::{remove_words_from_sentence, init};
 ///
-/// let mut buffer: Vec<u8> = vec![];
-/// buffer.extend_from_slice(b"ich");
-/// buffer.extend_from_slice(b"\n");
-///
-/// buffer.extend_from_slice(b"bin");
-/// buffer.extend_from_slice(b"\n");
+/// let mut file = File::create("./stopwords").unwrap();
+/// file.write_all(b"julia\n").unwrap();
 ///
-/// let mut file = File::create("./stopwords.txt").unwrap();
-/// file.write_all(&buffer).unwrap();
-/// 
-/// init(Path::new("./stopwords").to_path_buf());
+/// init(Path::new("./stopwords").to_path_buf()).unwrap();
 ///
-/// let sentence = "ich bin Hans".to_string();
-/// assert_eq!(remove_words_from_sentence(sentence), "Hans".to_string());
+/// let sentence = "ich bin Hans und du bist Julia".to_string();
+/// assert_eq!(remove_words_from_sentence(sentence, "de"), "Hans".to_string());
 /// ```
-pub fn remove_words_from_sentence(sentence: String) -> String {
-    let stop_words = STOP_WORDS.get_or_init(Vec::default);
-
+pub fn remove_words_from_sentence(sentence: String, lang: &str) -> String {
     sentence
         .split_whitespace()
-        .filter(|word| !stop_words.contains(&word.to_lowercase()))
+        .filter(|word| !is_stopword(word, lang))
         .collect::<Vec<&str>>()
         .join(" ")
 }
+
+/// Whether a word is a stop word of the default service in the given language.
+pub fn is_stopword(word: &str, lang: &str) -> bool {
+    registry().get("", lang).contains(&word.to_lowercase())
+}
+
+#[cfg(all(test, feature = "fr", feature = "en"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin() {
+        assert!(is_stopword("Les", "fr"));
+        assert!(is_stopword("the", "en"));
+        assert!(!is_stopword("the", "fr"));
+        assert!(!is_stopword("les", "xx"));
+    }
+
+    #[test]
+    fn test_registry_reload() {
+        let path = std::env::temp_dir().join("squid_test_registry_reload");
+        std::fs::write(&path, "rt\n").unwrap();
+
+        let registry = Registry::default();
+        registry.register("news", path.clone()).unwrap();
+        let before = registry.get("news", "fr");
+
+        std::fs::write(&path, "via").unwrap();
+        registry.reload("news").unwrap();
+        let after = registry.get("news", "fr");
+
+        assert!(before.contains("rt") && before.contains("les"));
+        assert!(!after.contains("rt") && after.contains("via"));
+        assert!(!registry.get("sport", "fr").contains("via"));
+        assert!(Arc::ptr_eq(
+            &registry.get("news", "xx"),
+            &registry.get("news", "yy")
+        ));
+
+        registry.append("news", ["Thread"]).unwrap();
+        assert!(registry.get("news", "fr").contains("thread"));
+        assert!(registry.append("sport", ["thread"]).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(registry.reload("news").is_err());
+        assert!(registry.get("news", "fr").contains("via"));
+    }
+}
@@ -0,0 +1,324 @@
+//! transliteration of Cyrillic and Greek to Latin script.
+//!
+//! Feeds mix "Зеленский" and "Zelensky", or "Τσιτσιπάς" and "Tsitsipas", so
+//! words can be counted with a Latin key. Cyrillic letters are transliterated
+//! one by one, without the contextual rules of some systems (such as "ye" for
+//! "е" at the start of a word), so keys stay stable. Greek letters follow the
+//! digraphs of both systems, such as "ou" for "ου" and "ev" for "ευ".
+//!
+//! Ukrainian "и" and "г" are "y" and "h" with BGN/PCGN when the language of
+//! the text is Ukrainian, or when the word has a letter only Ukrainian uses.
+
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+
+/// Transliteration system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheme {
+    /// ISO 9 for Cyrillic and ISO 843 for Greek, which are reversible and
+    /// use diacritics, such as "ž" for "ж".
+    #[default]
+    Iso,
+    /// BGN/PCGN, which uses digraphs, such as "zh" for "ж".
+    Bgn,
+}
+
+/// Cyrillic letters with their ISO 9 and BGN/PCGN transliterations.
+const CYRILLIC: [(char, &str, &str); 50] = [
+    ('а', "a", "a"),
+    ('б', "b", "b"),
+    ('в', "v", "v"),
+    ('г', "g", "g"),
+    ('д', "d", "d"),
+    ('е', "e", "e"),
+    ('ё', "ë", "ë"),
+    ('ж', "ž", "zh"),
+    ('з', "z", "z"),
+    ('и', "i", "i"),
+    ('й', "j", "y"),
+    ('к', "k", "k"),
+    ('л', "l", "l"),
+    ('м', "m", "m"),
+    ('н', "n", "n"),
+    ('о', "o", "o"),
+    ('п', "p", "p"),
+    ('р', "r", "r"),
+    ('с', "s", "s"),
+    ('т', "t", "t"),
+    ('у', "u", "u"),
+    ('ф', "f", "f"),
+    ('х', "h", "kh"),
+    ('ц', "c", "ts"),
+    ('ч', "č", "ch"),
+    ('ш', "š", "sh"),
+    ('щ', "ŝ", "shch"),
+    ('ъ', "ʺ", "ʺ"),
+    ('ы', "y", "y"),
+    ('ь', "ʹ", "ʹ"),
+    ('э', "è", "e"),
+    ('ю', "û", "yu"),
+    ('я', "â", "ya"),
+    // Ukrainian.
+    ('є', "ê", "ye"),
+    ('і', "ì", "i"),
+    ('ї', "ï", "yi"),
+    ('ґ', "g̀", "g"),
+    // Belarusian.
+    ('ў', "ŭ", "ŭ"),
+    // Serbian and Macedonian.
+    ('ђ', "đ", "dj"),
+    ('ѓ', "ǵ", "gj"),
+    ('ѕ', "ẑ", "dz"),
+    ('ј', "ǰ", "j"),
+    ('љ', "l̂", "lj"),
+    ('њ', "n̂", "nj"),
+    ('ћ', "ć", "ć"),
+    ('ќ', "ḱ", "kj"),
+    ('џ', "d̂", "dž"),
+    // Old orthography.
+    ('ѣ', "ě", "ě"),
+    ('ѳ', "f̀", "f"),
+    ('ѵ', "ỳ", "i"),
+];
+
+/// Greek letters with their ISO 843 and BGN/PCGN transliterations.
+const GREEK: [(char, &str, &str); 25] = [
+    ('α', "a", "a"),
+    ('β', "v", "v"),
+    ('γ', "g", "g"),
+    ('δ', "d", "d"),
+    ('ε', "e", "e"),
+    ('ζ', "z", "z"),
+    ('η', "ī", "i"),
+    ('θ', "th", "th"),
+    ('ι', "i", "i"),
+    ('κ', "k", "k"),
+    ('λ', "l", "l"),
+    ('μ', "m", "m"),
+    ('ν', "n", "n"),
+    ('ξ', "x", "x"),
+    ('ο', "o", "o"),
+    ('π', "p", "p"),
+    ('ρ', "r", "r"),
+    ('σ', "s", "s"),
+    ('ς', "s", "s"),
+    ('τ', "t", "t"),
+    ('υ', "y", "y"),
+    ('φ', "f", "f"),
+    ('χ', "ch", "kh"),
+    ('ψ', "ps", "ps"),
+    ('ω', "ō", "o"),
+];
+
+/// Whether a character is a Cyrillic or Greek letter.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::transliterate::is_transliterated;
+///
+/// assert!(is_transliterated('ж') && is_transliterated('Ω'));
+/// assert!(!is_transliterated('z'));
+/// ```
+pub fn is_transliterated(c: char) -> bool {
+    matches!(
+        c as u32,
+        // Greek.
+        0x0370..=0x03FF
+            | 0x1F00..=0x1FFF
+            // Cyrillic.
+            | 0x0400..=0x052F
+            | 0x1C80..=0x1C8F
+            | 0x2DE0..=0x2DFF
+            | 0xA640..=0xA69F
+    )
+}
+
+/// Greek consonants pronounced without voice. "υ" reads as "f" after "α",
+/// "ε" and "η" before them.
+const VOICELESS: [char; 10] = ['θ', 'κ', 'ξ', 'π', 'σ', 'ς', 'τ', 'φ', 'χ', 'ψ'];
+/// Letters only found in Ukrainian among the languages using Cyrillic.
+const UKRAINIAN: [char; 3] = ['є', 'ї', 'ґ'];
+
+/// Transliterates Cyrillic and Greek letters of a text, in a language given
+/// as an ISO 639-1 code, to Latin script.
+///
+/// Letters are lowercased, and Greek accents are removed. A diaeresis, as in
+/// "ϊ", keeps a Greek vowel apart from the previous one. Other characters are
+/// kept.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::transliterate::{to_latin, Scheme};
+///
+/// assert_eq!(to_latin("Зеленский", Scheme::Bgn, ""), "zelenskiy");
+/// assert_eq!(to_latin("жена", Scheme::Iso, ""), "žena");
+/// assert_eq!(to_latin("#Τσιτσιπάς", Scheme::Bgn, ""), "#tsitsipas");
+/// assert_eq!(to_latin("Ευρώπη", Scheme::Bgn, ""), "evropi");
+/// assert_eq!(to_latin("Харків", Scheme::Bgn, "uk"), "kharkiv");
+/// assert_eq!(to_latin("mbappé", Scheme::Iso, ""), "mbappé");
+/// ```
+pub fn to_latin<'a>(text: &'a str, scheme: Scheme, lang: &str) -> Cow<'a, str> {
+    if !text.chars().any(is_transliterated) {
+        return Cow::Borrowed(text);
+    }
+
+    // Lowercase letters without accents, with whether they had a diaeresis.
+    let mut letters: Vec<(char, bool)> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if !is_transliterated(c) {
+            letters.push((c, false));
+            continue;
+        }
+
+        for c in c.to_lowercase() {
+            if letter(c, scheme).is_some() {
+                letters.push((c, false));
+                continue;
+            }
+
+            // Greek letters with accents, such as "ά".
+            let mut decomposed = c.to_string().nfd().collect::<Vec<_>>().into_iter();
+            match decomposed.next() {
+                Some(base) if letter(base, scheme).is_some() => {
+                    letters.push((base, decomposed.any(|mark| mark == '\u{0308}')))
+                }
+                _ => letters.push((c, false)),
+            }
+        }
+    }
+
+    let ukrainian = scheme == Scheme::Bgn
+        && (lang == "uk" || letters.iter().any(|(c, _)| UKRAINIAN.contains(c)));
+
+    let mut result = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < letters.len() {
+        let (c, _) = letters[index];
+        let initial = index == 0 || !is_greek(letters[index - 1].0);
+        let next = letters.get(index + 1).copied();
+        let following = letters.get(index + 2).map(|(c, _)| *c);
+
+        if let Some(latin) = next.and_then(|next| digraph(c, next, following, initial, scheme)) {
+            result.push_str(latin);
+            index += 2;
+            continue;
+        }
+
+        match (c, ukrainian) {
+            ('и', true) => result.push('y'),
+            ('г', true) => result.push('h'),
+            _ => match letter(c, scheme) {
+                Some(latin) => result.push_str(latin),
+                None => result.push(c),
+            },
+        }
+        index += 1;
+    }
+
+    Cow::Owned(result)
+}
+
+/// Returns the transliteration of two lowercase Greek letters read together,
+/// with the letter following them and whether they start a word.
+fn digraph(
+    first: char,
+    (second, diaeresis): (char, bool),
+    following: Option<char>,
+    initial: bool,
+    scheme: Scheme,
+) -> Option<&'static str> {
+    if second == 'υ' {
+        if diaeresis {
+            return None;
+        }
+
+        let voiceless = following.is_none_or(|c| !is_greek(c) || VOICELESS.contains(&c));
+        return match (first, voiceless, scheme) {
+            ('ο', _, _) => Some("ou"),
+            ('α', false, _) => Some("av"),
+            ('α', true, _) => Some("af"),
+            ('ε', false, _) => Some("ev"),
+            ('ε', true, _) => Some("ef"),
+            ('η', false, Scheme::Iso) => Some("īv"),
+            ('η', true, Scheme::Iso) => Some("īf"),
+            ('η', false, Scheme::Bgn) => Some("iv"),
+            ('η', true, Scheme::Bgn) => Some("if"),
+            _ => None,
+        };
+    }
+
+    match (first, second, scheme) {
+        ('γ', 'γ', _) => Some("ng"),
+        ('γ', 'ξ', _) => Some("nx"),
+        ('γ', 'χ', Scheme::Iso) => Some("nch"),
+        ('γ', 'χ', Scheme::Bgn) => Some("nkh"),
+        // BGN/PCGN writes the sounds of these pairs, which depend on their
+        // place in the word.
+        ('μ', 'π', Scheme::Bgn) => Some(if initial { "b" } else { "mb" }),
+        ('ν', 'τ', Scheme::Bgn) => Some(if initial { "d" } else { "nd" }),
+        ('γ', 'κ', Scheme::Bgn) => Some(if initial { "g" } else { "ng" }),
+        _ => None,
+    }
+}
+
+/// Whether a lowercase letter without accents is Greek.
+fn is_greek(c: char) -> bool {
+    GREEK.iter().any(|(letter, _, _)| *letter == c)
+}
+
+/// Returns the transliteration of a lowercase letter.
+fn letter(c: char, scheme: Scheme) -> Option<&'static str> {
+    CYRILLIC
+        .iter()
+        .chain(GREEK.iter())
+        .find(|(letter, _, _)| *letter == c)
+        .map(|(_, iso, bgn)| match scheme {
+            Scheme::Iso => *iso,
+            Scheme::Bgn => *bgn,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_latin() {
+        assert_eq!(to_latin("Щорс", Scheme::Iso, ""), "ŝors");
+        assert_eq!(to_latin("Щорс", Scheme::Bgn, ""), "shchors");
+        assert_eq!(to_latin("Ἀθῆναι", Scheme::Iso, ""), "athīnai");
+        assert_eq!(to_latin("Χάρης", Scheme::Bgn, ""), "kharis");
+        assert_eq!(to_latin("путин2024", Scheme::Bgn, ""), "putin2024");
+        assert_eq!(to_latin("йёлка", Scheme::Iso, ""), "jëlka");
+    }
+
+    #[test]
+    fn test_greek_digraphs() {
+        assert_eq!(to_latin("Πούτιν", Scheme::Iso, ""), "poutin");
+        assert_eq!(to_latin("Ευρώπη", Scheme::Iso, ""), "evrōpī");
+        assert_eq!(to_latin("Ευρώπη", Scheme::Bgn, ""), "evropi");
+        assert_eq!(to_latin("αυτοκίνητο", Scheme::Bgn, ""), "aftokinito");
+        assert_eq!(to_latin("Ζευς", Scheme::Bgn, ""), "zefs");
+        assert_eq!(to_latin("ευ", Scheme::Bgn, ""), "ef");
+        // A diaeresis keeps the vowels apart.
+        assert_eq!(to_latin("προϋπόθεση", Scheme::Bgn, ""), "proypothesi");
+
+        assert_eq!(to_latin("Μπακογιάννης", Scheme::Bgn, ""), "bakogiannis");
+        assert_eq!(to_latin("Μπακογιάννης", Scheme::Iso, ""), "mpakogiannīs");
+        assert_eq!(to_latin("Ολυμπιακός", Scheme::Bgn, ""), "olymbiakos");
+        assert_eq!(to_latin("ντομάτα", Scheme::Bgn, ""), "domata");
+        assert_eq!(to_latin("Άγγελος", Scheme::Iso, ""), "angelos");
+        assert_eq!(to_latin("Άγγελος", Scheme::Bgn, ""), "angelos");
+    }
+
+    #[test]
+    fn test_ukrainian() {
+        assert_eq!(to_latin("Україна", Scheme::Bgn, ""), "ukrayina");
+        assert_eq!(to_latin("Харків", Scheme::Bgn, "uk"), "kharkiv");
+        assert_eq!(to_latin("Григорій", Scheme::Bgn, "uk"), "hryhoriy");
+        assert_eq!(to_latin("Григорий", Scheme::Bgn, "ru"), "grigoriy");
+        assert_eq!(to_latin("Київ", Scheme::Bgn, ""), "kyyiv");
+        // ISO 9 does not depend on the language.
+        assert_eq!(to_latin("Григорій", Scheme::Iso, "uk"), "grigorìj");
+    }
+}
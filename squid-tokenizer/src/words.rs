@@ -0,0 +1,94 @@
+//! word boundaries of scripts written without spaces.
+//!
+//! Chinese, Japanese or Thai texts do not separate words with spaces, so
+//! runs of these scripts are split following the Unicode word boundary rules
+//! (UAX #29). Each ideograph is then a word, and katakana stay grouped.
+//! Thai runs stay whole unless they contain zero width spaces, as their word
+//! boundaries require a dictionary. Other scripts are split on whitespace
+//! only, so hashtags, mentions and links stay whole.
+
+use std::{iter, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Whether a character belongs to a script written without spaces between
+/// words.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::words::is_unspaced;
+///
+/// assert!(is_unspaced('東'));
+/// assert!(!is_unspaced('é'));
+/// ```
+pub fn is_unspaced(c: char) -> bool {
+    matches!(
+        c as u32,
+        // Thai, Lao.
+        0x0E00..=0x0EFF
+            // Myanmar.
+            | 0x1000..=0x109F
+            // Khmer.
+            | 0x1780..=0x17FF
+            // Hiragana, Katakana.
+            | 0x3040..=0x30FF
+            | 0x31F0..=0x31FF
+            // CJK ideographs.
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xF900..=0xFAFF
+            | 0x20000..=0x2FA1F
+            // Halfwidth katakana.
+            | 0xFF66..=0xFF9F
+    )
+}
+
+/// Splits a chunk of text without whitespace into byte ranges of words,
+/// lazily.
+///
+/// Chunks without characters of [unspaced](is_unspaced) scripts are kept
+/// whole. Otherwise, pieces without letters nor digits are dropped.
+///
+/// # Example
+/// ```rust
+/// use squid_tokenizer::words::split;
+///
+/// let text = "東京オリンピック2024";
+/// let words: Vec<&str> = split(text).map(|range| &text[range]).collect();
+///
+/// assert_eq!(words, vec!["東", "京", "オリンピック", "2024"]);
+/// ```
+pub fn split(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let whole = text.is_ascii() || !text.chars().any(is_unspaced);
+    // Only unspaced chunks are segmented.
+    let unspaced = if whole { "" } else { text };
+
+    iter::once(0..text.len()).filter(move |_| whole).chain(
+        unspaced
+            .split_word_bound_indices()
+            .filter(|(_, word)| word.chars().any(char::is_alphanumeric))
+            .map(|(index, word)| index..index + word.len()),
+    )
+}
+
+/// Whether a character splits two chunks of text.
+///
+/// Any Unicode whitespace is a separator, including no-break spaces used in
+/// French before `!` and `?`, and the zero width space used in Thai.
+pub fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '\u{200B}'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let text = "我爱北京";
+        let words: Vec<&str> = split(text).map(|range| &text[range]).collect();
+
+        assert_eq!(words, vec!["我", "爱", "北", "京"]);
+        assert_eq!(split("#8mars").collect::<Vec<_>>(), vec![0..6]);
+        assert!(is_separator('\u{202F}') && is_separator('\u{A0}'));
+    }
+}
@@ -1,5 +1,5 @@
 use squid::squid_client::SquidClient;
-use squid::LeaderboardRequest;
+use squid::{Flagged, Kind, LeaderboardRequest};
 
 pub mod squid {
     tonic::include_proto!("squid");
@@ -17,7 +17,11 @@ async fn main() {
     let response = SquidClient::connect("http://localhost:50051")
         .await
         .unwrap()
-        .leaderboard(LeaderboardRequest { length: 10 })
+        .leaderboard(LeaderboardRequest {
+            length: 10,
+            kind: Kind::Any.into(),
+            flagged: Flagged::Include.into(),
+        })
         .await
         .unwrap()
         .into_inner();
@@ -40,6 +40,7 @@ async fn main() {
             .add(AddRequest {
                 sentence: sentence.to_string(),
                 lifetime: 10,
+                lang: String::default(), // detected by Squid.
             })
             .await
             .unwrap()
@@ -1,10 +1,21 @@
-use crate::models::database::Entity;
-use squid_algorithm::hashtable::MapAlgorithm;
+use crate::models::{
+    config::{Config, MessageType},
+    database::Entity,
+};
+use squid_algorithm::{
+    hashtable::{Counter, Kind, MapAlgorithm, Occurrence},
+    variants::{self, Merge, Options},
+};
 use squid_db::Instance;
 use squid_error::Error;
-use std::sync::Arc;
+use squid_tokenizer::{config::TokenizerConfig, tokens, Token, TokenKind};
+use std::{borrow::Cow, fs, io, path::Path, sync::Arc};
 use tokio::sync::RwLock;
 
+/// Replaces spaces between the words of phrases in keys, since stored texts
+/// are words separated by spaces.
+pub const SPACE: &str = "%20";
+
 /// The algorithms managed by Squid.
 #[derive(Debug, Clone)]
 pub enum Algorithm {
@@ -18,32 +29,138 @@ impl From<MapAlgorithm> for Algorithm {
     }
 }
 
-/// Adds a value to the database and the algorithm.
+/// Returns the key of a token, as counted and stored.
+pub fn key(text: &str) -> Cow<'_, str> {
+    if text.contains(' ') {
+        Cow::Owned(text.replace(' ', SPACE))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Guesses the kind of a token from its key, for entities saved before
+/// kinds were stored.
+pub fn kind(key: &str) -> TokenKind {
+    if key.contains(SPACE) {
+        TokenKind::of(&key.replace(SPACE, " "))
+    } else {
+        TokenKind::of(key)
+    }
+}
+
+/// Returns the kind of a token, as counted by the algorithm.
+pub fn counted_kind(kind: TokenKind) -> Kind {
+    match kind {
+        TokenKind::Word => Kind::Word,
+        TokenKind::Hashtag => Kind::Hashtag,
+        TokenKind::Mention => Kind::Mention,
+        TokenKind::Url => Kind::Url,
+        TokenKind::Email => Kind::Email,
+        TokenKind::Emoji => Kind::Emoji,
+        TokenKind::Number => Kind::Number,
+        TokenKind::Date => Kind::Date,
+        TokenKind::Phrase => Kind::Phrase,
+    }
+}
+
+/// Returns the words to exclude as written and as counted, so they are still
+/// excluded once words are stemmed, lemmatized or folded.
+///
+/// Words are processed in the language of the service. Words of a phrase,
+/// such as "Aya Nakamura", are processed as the key of the phrase.
+pub fn exclusions(config: &Config, tokenizer: &TokenizerConfig) -> Vec<String> {
+    let lang = config
+        .service
+        .lang
+        .as_deref()
+        .unwrap_or(&config.service.default_lang);
+    let phrase = TokenizerConfig {
+        remove_stopwords: false,
+        lemmatize: false,
+        stem: false,
+        phrases: false,
+        ..tokenizer.clone()
+    };
+
+    let mut exclusions = Vec::new();
+    for exclude in &config.service.exclude {
+        exclusions.push(key(exclude).into_owned());
+
+        let words: Vec<Token> = tokens(exclude)
+            .config(tokenizer)
+            .service(&config.service.name)
+            .lang(lang)
+            .collect();
+        let processed = match words.as_slice() {
+            [word] => key(&word.text).into_owned(),
+            [] => continue,
+            _ => key(
+                &tokens(exclude)
+                    .config(&phrase)
+                    .service(&config.service.name)
+                    .lang(lang)
+                    .map(|word| word.text)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )
+            .into_owned(),
+        };
+        exclusions.push(processed);
+    }
+
+    exclusions.sort_unstable();
+    exclusions.dedup();
+    exclusions
+}
+
+/// Whether a key must be counted by the algorithm.
+///
+/// Words to exclude are expected to be processed by [`exclusions`].
+pub fn is_counted(config: &Config, word: &str, kind: TokenKind) -> bool {
+    if config.service.exclude.iter().any(|exclude| key(exclude) == word) {
+        return false;
+    }
+
+    match config.service.message_type {
+        MessageType::Hashtag => kind == TokenKind::Hashtag,
+        MessageType::Word => kind != TokenKind::Hashtag,
+        MessageType::Anything => true,
+    }
+}
+
+/// Whether words written in a language must be counted by the algorithm.
+pub fn is_lang_counted(config: &Config, lang: &str) -> bool {
+    config.service.lang.as_ref().is_none_or(|service| service == lang)
+}
+
+/// Adds a value to the database and its tokens to the algorithm.
 pub async fn set<A: Into<Algorithm>>(
-    config: &crate::models::config::Config,
+    config: &Config,
     instance: Arc<RwLock<Instance<Entity>>>,
     algorithm: A,
     value: Entity,
+    tokens: &[Token<'_>],
 ) -> Result<(), Error> {
-    instance.write().await.set(value.clone()).await?;
+    let counted = is_lang_counted(config, &value.lang);
+    instance.write().await.set(value).await?;
+
+    if !counted {
+        return Ok(());
+    }
 
     match algorithm.into() {
         Algorithm::Map(implementation) => {
-            for str in value.post_processing_text.split_whitespace() {
-                if !config.service.exclude.contains(&str.to_string()) {
-                    match config.service.message_type {
-                        crate::models::config::MessageType::Hashtag => {
-                            if str.starts_with('#') {
-                                implementation.write().await.set(str)
-                            }
-                        },
-                        crate::models::config::MessageType::Word => {
-                            if !str.starts_with('#') {
-                                implementation.write().await.set(str)
-                            }
+            for token in tokens {
+                let key = key(&token.text);
+                if is_counted(config, &key, token.kind) {
+                    implementation.write().await.add(
+                        key,
+                        Occurrence {
+                            display: Some(&token.display),
+                            kind: counted_kind(token.kind),
+                            flagged: token.flagged,
                         },
-                        _ => implementation.write().await.set(str),
-                    }
+                    )
                 }
             }
         },
@@ -52,28 +169,131 @@ pub async fn set<A: Into<Algorithm>>(
     Ok(())
 }
 
+/// Removes every occurrence of a key from the algorithm.
+pub async fn delete<A: Into<Algorithm>>(algorithm: A, key: &str) {
+    match algorithm.into() {
+        Algorithm::Map(implementation) => {
+            implementation.write().await.delete(key)
+        },
+    }
+}
+
+/// Finds spelling variants among counted words.
+///
+/// Words are compared on a blocking thread, without holding the algorithm,
+/// not to delay texts being counted.
+pub async fn variants<A: Into<Algorithm>>(
+    algorithm: A,
+    options: &Options,
+) -> Vec<Merge> {
+    let counts = match algorithm.into() {
+        Algorithm::Map(implementation) => implementation.read().await.counts(),
+    };
+
+    let options = *options;
+    tokio::task::spawn_blocking(move || variants::find(counts, &options))
+        .await
+        .unwrap_or_default()
+}
+
+/// Counts a word as another one.
+pub async fn merge<A: Into<Algorithm>>(
+    algorithm: A,
+    variant: &str,
+    canonical: &str,
+) {
+    match algorithm.into() {
+        Algorithm::Map(implementation) => {
+            implementation.write().await.merge(variant, canonical)
+        },
+    }
+}
+
+/// Merges spelling variants again, from a file written by [`save_aliases`].
+///
+/// Returns the number of merged variants.
+pub async fn load_aliases<A: Into<Algorithm>>(
+    algorithm: A,
+    path: &Path,
+) -> io::Result<usize> {
+    let aliases = fs::read_to_string(path)?;
+    let aliases: Vec<(&str, &str)> =
+        aliases.lines().filter_map(|line| line.split_once('\t')).collect();
+
+    match algorithm.into() {
+        Algorithm::Map(implementation) => {
+            let mut implementation = implementation.write().await;
+            for (variant, canonical) in &aliases {
+                implementation.merge(variant, canonical);
+            }
+        },
+    }
+
+    Ok(aliases.len())
+}
+
+/// Writes merged spelling variants, with a variant and its word separated by
+/// a tab on each line.
+pub async fn save_aliases<A: Into<Algorithm>>(
+    algorithm: A,
+    path: &Path,
+) -> io::Result<()> {
+    let aliases: String = match algorithm.into() {
+        Algorithm::Map(implementation) => implementation
+            .read()
+            .await
+            .aliases()
+            .map(|(variant, canonical)| format!("{}\t{}\n", variant, canonical))
+            .collect(),
+    };
+
+    // Written aside, then swapped, not to lose aliases on a crash.
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, aliases)?;
+    fs::rename(&temporary, path)
+}
+
 /// Removes a value to the algorithm.
 pub async fn _remove<A: Into<Algorithm>>(
     algorithm: A,
     key: String,
+    display: Option<&str>,
 ) -> Result<(), Error> {
     match algorithm.into() {
         Algorithm::Map(implementation) => {
-            implementation.write().await.remove(key)
+            implementation.write().await.remove(key, display)
         },
     }
 
     Ok(())
 }
 
-/// Rank the most used words.
-pub async fn rank<A: Into<Algorithm>>(
+/// Rank the most used words matching a filter, with their most frequent
+/// spelling.
+///
+/// Words without known spelling, such as those of entities saved before
+/// spellings were stored, are displayed as is.
+pub async fn rank<A, F>(
     algorithm: A,
     length: usize,
-) -> Vec<(String, usize)> {
+    filter: F,
+) -> Vec<(String, usize, String)>
+where
+    A: Into<Algorithm>,
+    F: Fn(&str, &Counter) -> bool,
+{
     match algorithm.into() {
         Algorithm::Map(implementation) => {
-            implementation.read().await.rank(length)
+            let implementation = implementation.read().await;
+            implementation
+                .rank_by(length, filter)
+                .into_iter()
+                .map(|(word, count)| {
+                    let display =
+                        implementation.display(&word).unwrap_or(&word).to_string();
+                    (word, count, display)
+                })
+                .collect()
         },
     }
 }
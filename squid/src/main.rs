@@ -9,18 +9,33 @@ extern crate lazy_static;
 use crate::models::database::Entity;
 use squid::{
     squid_server::{Squid, SquidServer},
-    {AddRequest, LeaderboardRequest, Ranking, Void, Word},
+    {
+        AddRequest, Candidate, Candidates, Flagged, Kind, LeaderboardRequest,
+        Merge, Merges, Ranking, StopWords, Void, Word,
+    },
+};
+use squid_algorithm::{
+    frequency::{self, DocumentFrequency},
+    hashtable::{self, Counter, Occurrence},
+    variants::{self, Options},
+};
+use squid_error::TokenizerError;
+use squid_tokenizer::{
+    check,
+    config::TokenizerConfig,
+    hashtag, language,
+    lemma::{self, TableFormat},
+    normalize, profanity, stopwords, tokens, Token, TokenKind,
 };
-use squid_tokenizer::tokenize;
 use std::{
     ops::Add,
-    sync::Arc,
+    sync::{Arc, Mutex, PoisonError},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::signal;
 use tokio::sync::{mpsc, RwLock};
 use tonic::{transport::Server, Request, Response, Status};
-use tracing::{error, info, Level};
+use tracing::{error, info, warn, Level};
 use tracing_subscriber::fmt;
 
 pub mod squid {
@@ -28,11 +43,23 @@ pub mod squid {
 }
 struct SuperSquid {
     algorithm: helpers::database::Algorithm,
-    config: models::config::Config,
+    config: Arc<models::config::Config>,
+    tokenizer: TokenizerConfig,
     instance: Arc<RwLock<squid_db::Instance<models::database::Entity>>>,
+    /// Words of counted texts over time, if stop words are discovered.
+    frequency: Option<Arc<RwLock<DocumentFrequency>>>,
+    /// Stop words proposed by the last analysis.
+    candidates: Arc<RwLock<Vec<frequency::Candidate>>>,
+    /// Spelling variants proposed by the last analysis, if they are looked
+    /// for.
+    variants: Option<Arc<RwLock<Vec<variants::Merge>>>>,
+    /// Words waiting to be learned by the hashtag dictionary.
+    learned: Mutex<Vec<String>>,
 }
 
 const FLUSHTABLE_FLUSH_SIZE_KB: usize = 100; // wait 100kb on memtable before save it on disk.
+const MIN_LANG_CONFIDENCE: f64 = 0.5; // under it, the service language is used.
+const LEARN_BATCH_SIZE: usize = 1_000; // words learned at once by the hashtag dictionary.
 
 #[tonic::async_trait]
 impl Squid for SuperSquid {
@@ -40,23 +67,133 @@ impl Squid for SuperSquid {
         &self,
         request: Request<LeaderboardRequest>,
     ) -> Result<Response<Ranking>, Status> {
+        let request = request.into_inner();
+        let kind = match request.kind() {
+            Kind::Any => None,
+            Kind::Word => Some(hashtable::Kind::Word),
+            Kind::Hashtag => Some(hashtable::Kind::Hashtag),
+            Kind::Mention => Some(hashtable::Kind::Mention),
+            Kind::Url => Some(hashtable::Kind::Url),
+            Kind::Email => Some(hashtable::Kind::Email),
+            Kind::Emoji => Some(hashtable::Kind::Emoji),
+            Kind::Phrase => Some(hashtable::Kind::Phrase),
+            Kind::Number => Some(hashtable::Kind::Number),
+            Kind::Date => Some(hashtable::Kind::Date),
+        };
+
+        let flagged = match request.flagged() {
+            Flagged::Include => None,
+            Flagged::Exclude => Some(false),
+            Flagged::Only => Some(true),
+        };
+
+        let filter = |_: &str, counter: &Counter| {
+            kind.is_none_or(|kind| counter.kind == kind)
+                && flagged.is_none_or(|flagged| counter.flagged == flagged)
+        };
+
         Ok(Response::new(Ranking {
-            word: helpers::database::rank(
-                self.algorithm.clone(),
-                request.into_inner().length as usize,
-            )
-            .await
-            .iter()
-            .map(|(word, occurence)| Word {
-                word: word.to_string().replace("%20", " "),
-                occurence: (*occurence).try_into().unwrap_or_default(),
-            })
-            .collect::<Vec<_>>(),
+            word: helpers::database::rank(self.algorithm.clone(), request.length as usize, filter)
+                .await
+                .iter()
+                .map(|(word, occurence, display)| Word {
+                    word: word.to_string().replace(helpers::database::SPACE, " "),
+                    occurence: (*occurence).try_into().unwrap_or_default(),
+                    display: display.to_string(),
+                })
+                .collect::<Vec<_>>(),
         }))
     }
 
     async fn add(&self, request: Request<AddRequest>) -> Result<Response<Void>, Status> {
         let data = request.into_inner();
+        check(&data.sentence, &self.tokenizer)
+            .map_err(|error| Status::invalid_argument(error.etype.to_string()))?;
+
+        let lang = if !data.lang.is_empty() {
+            let lang = data.lang.to_lowercase();
+            if !language::is_code(&lang) {
+                return Err(Status::invalid_argument(
+                    "language must be an ISO 639-1 code",
+                ));
+            }
+            lang
+        } else if let Some(detection) = self
+            .detect(&data.sentence)
+            .filter(|detection| detection.confidence >= MIN_LANG_CONFIDENCE)
+        {
+            detection.lang.to_string()
+        } else {
+            self.config
+                .service
+                .lang
+                .clone()
+                .unwrap_or_else(|| self.config.service.default_lang.clone())
+        };
+        let tokens: Vec<Token> = tokens(&data.sentence)
+            .config(&self.tokenizer)
+            .service(&self.config.service.name)
+            .lang(&lang)
+            .collect();
+
+        if tokens.is_empty() {
+            return Err(Status::invalid_argument(TokenizerError::Empty.to_string()));
+        }
+
+        if self.tokenizer.segment_hashtags {
+            // Learn spellings of words to split next hashtags, in batches
+            // not to lock the dictionary for every text.
+            let words = tokens
+                .iter()
+                .filter(|token| token.kind == TokenKind::Word)
+                .map(|token| {
+                    normalize::normalize(&data.sentence[token.byte_range.clone()]).into_owned()
+                });
+            let batch = {
+                let mut learned = self.learned.lock().unwrap_or_else(PoisonError::into_inner);
+                learned.extend(words);
+                (learned.len() >= LEARN_BATCH_SIZE).then(|| std::mem::take(&mut *learned))
+            };
+
+            if let Some(batch) = batch {
+                let mut dictionary = hashtag::dictionary()
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner);
+                for word in &batch {
+                    dictionary.learn(word);
+                }
+            }
+        }
+
+        let mut meta = Vec::new();
+        if data.lifetime != 0 {
+            meta.push(format!(
+                "expire_at:{}",
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .add(Duration::from_secs(data.lifetime))
+                    .as_secs()
+            ));
+        }
+        if tokens.iter().any(|token| token.flagged) {
+            meta.push("flagged".to_string());
+        }
+        let meta = meta.join(",");
+
+        if let Some(frequency) = self
+            .frequency
+            .as_ref()
+            .filter(|_| helpers::database::is_lang_counted(&self.config, &lang))
+        {
+            frequency.write().await.add(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                tokens.iter().map(|token| helpers::database::key(&token.text)),
+            );
+        }
 
         helpers::database::set(
             &self.config,
@@ -65,30 +202,231 @@ impl Squid for SuperSquid {
             models::database::Entity {
                 id: uuid::Uuid::new_v4().to_string(),
                 original_text: None,
-                post_processing_text: tokenize(&data.sentence).map_err(|error| {
-                    error!("Failed to tokenize {:?}: {}", data.sentence, error);
-                    Status::invalid_argument("failed to tokenize sentence")
-                })?,
-                lang: "fr".to_string(),
-                meta: if data.lifetime == 0 {
-                    String::default()
-                } else {
-                    format!(
-                        "expire_at:{}",
-                        SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .add(Duration::from_secs(data.lifetime))
-                            .as_secs()
-                    )
-                },
+                post_processing_text: tokens
+                    .iter()
+                    .map(|token| helpers::database::key(&token.text))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                lang: lang.clone(),
+                meta,
+                tokens: tokens
+                    .iter()
+                    .map(|token| models::database::StoredToken {
+                        key: helpers::database::key(&token.text).into_owned(),
+                        display: token.display.to_string(),
+                        kind: token.kind.into(),
+                        flagged: token.flagged,
+                    })
+                    .collect(),
             },
+            &tokens,
         )
         .await
         .unwrap();
 
         Ok(Response::new(Void {}))
     }
+
+    async fn reload_stop_words(&self, request: Request<Void>) -> Result<Response<Void>, Status> {
+        self.authorize(&request)?;
+
+        stopwords::registry()
+            .reload(&self.config.service.name)
+            .map_err(|error| {
+                error!("Failed to reload stop words: {}", error);
+                Status::failed_precondition("failed to read stop words file")
+            })?;
+        info!("Stop words reloaded.");
+
+        Ok(Response::new(Void {}))
+    }
+
+    async fn stop_word_candidates(
+        &self,
+        _: Request<Void>,
+    ) -> Result<Response<Candidates>, Status> {
+        if self.frequency.is_none() {
+            return Err(Status::failed_precondition(
+                "stop word discovery is disabled",
+            ));
+        }
+
+        Ok(Response::new(Candidates {
+            candidate: self
+                .candidates
+                .read()
+                .await
+                .iter()
+                .map(|candidate| Candidate {
+                    word: candidate.word.replace(helpers::database::SPACE, " "),
+                    frequency: candidate.frequency,
+                    variation: candidate.variation,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn add_stop_words(
+        &self,
+        request: Request<StopWords>,
+    ) -> Result<Response<Void>, Status> {
+        self.authorize(&request)?;
+
+        let words: Vec<String> = request
+            .into_inner()
+            .word
+            .iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Err(Status::invalid_argument("no stop word to add"));
+        }
+
+        stopwords::registry()
+            .append(&self.config.service.name, &words)
+            .map_err(|error| {
+                error!("Failed to add stop words: {}", error);
+                Status::failed_precondition("failed to write stop words file")
+            })?;
+
+        // Stop words are no longer counted, nor proposed.
+        for word in &words {
+            let key = helpers::database::key(word);
+            helpers::database::delete(self.algorithm.clone(), &key).await;
+            if let Some(frequency) = &self.frequency {
+                frequency.write().await.remove(&key);
+            }
+        }
+        self.candidates.write().await.retain(|candidate| {
+            !words
+                .iter()
+                .any(|word| helpers::database::key(word) == candidate.word)
+        });
+        info!("Added {} stop words.", words.len());
+        // Merges into deleted words are forgotten too.
+        self.save_aliases().await;
+
+        Ok(Response::new(Void {}))
+    }
+
+    async fn spelling_variants(
+        &self,
+        _: Request<Void>,
+    ) -> Result<Response<Merges>, Status> {
+        let variants = self.variants.as_ref().ok_or_else(|| {
+            Status::failed_precondition("spelling variants are disabled")
+        })?;
+
+        Ok(Response::new(Merges {
+            merge: variants
+                .read()
+                .await
+                .iter()
+                .map(|merge| Merge {
+                    variant: merge.variant.clone(),
+                    canonical: merge.canonical.clone(),
+                    distance: merge.distance.try_into().unwrap_or_default(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn merge_variants(
+        &self,
+        request: Request<Merges>,
+    ) -> Result<Response<Void>, Status> {
+        self.authorize(&request)?;
+
+        let merges = request.into_inner().merge;
+        if merges.is_empty() {
+            return Err(Status::invalid_argument("no spelling variant to merge"));
+        }
+        if merges.iter().any(|merge| {
+            merge.variant.trim().is_empty()
+                || merge.canonical.trim().is_empty()
+                || merge.variant == merge.canonical
+        }) {
+            return Err(Status::invalid_argument(
+                "variants and canonical words must be distinct and not empty",
+            ));
+        }
+
+        for merge in &merges {
+            helpers::database::merge(
+                self.algorithm.clone(),
+                &merge.variant,
+                &merge.canonical,
+            )
+            .await;
+        }
+        if let Some(variants) = &self.variants {
+            variants.write().await.retain(|proposed| {
+                !merges.iter().any(|merge| merge.variant == proposed.variant)
+            });
+        }
+        info!("Merged {} spelling variants.", merges.len());
+        self.save_aliases().await;
+
+        Ok(Response::new(Void {}))
+    }
+}
+
+impl SuperSquid {
+    /// Refuses a request to an administration RPC without the configured
+    /// admin token, sent as `authorization: Bearer <token>` metadata.
+    ///
+    /// These RPCs change counting for every client, so they are refused
+    /// when no token is configured.
+    #[allow(clippy::result_large_err)]
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let expected = self
+            .config
+            .admin_token
+            .as_deref()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Status::permission_denied("administration RPCs are disabled"))?;
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+
+        // Compared in constant time, not to leak the token.
+        let valid = token.len() == expected.len()
+            && token
+                .bytes()
+                .zip(expected.bytes())
+                .fold(0, |difference, (a, b)| difference | (a ^ b))
+                == 0;
+        if valid {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("invalid admin token"))
+        }
+    }
+
+    /// Identifies the language of a text, among the languages of the
+    /// service if any.
+    fn detect(&self, text: &str) -> Option<language::Detection> {
+        let langs = &self.config.service.langs;
+        if langs.is_empty() {
+            language::detect(text)
+        } else {
+            let langs: Vec<&str> = langs.iter().map(String::as_str).collect();
+            language::detect_among(text, &langs)
+        }
+    }
+
+    /// Keeps merged spelling variants for the next start, if set.
+    async fn save_aliases(&self) {
+        if let Some(path) = &self.config.service.spelling_variants.aliases {
+            if let Err(err) = helpers::database::save_aliases(self.algorithm.clone(), path).await {
+                error!("Merged variants not saved to {:?}: {}", path, err);
+            }
+        }
+    }
 }
 
 #[tokio::main]
@@ -109,7 +447,58 @@ async fn main() {
         .with_max_level(Level::TRACE)
         .init();
 
-    let config = helpers::config::read();
+    let mut config = helpers::config::read();
+
+    let tokenizer = TokenizerConfig::from(&config.service.tokenizer);
+
+    // Languages detection cannot choose are ignored.
+    for lang in config.service.langs.iter().filter(|lang| !language::is_code(lang)) {
+        warn!("Language {:?} of the service is not an ISO 639-1 code.", lang);
+    }
+
+    // Load custom stop words on top of built-in ones.
+    if let Some(path) = &config.service.tokenizer.stopwords {
+        if let Err(err) = stopwords::registry().register(&config.service.name, path.clone()) {
+            warn!("Custom stop words not loaded from {:?}: {}", path, err);
+        }
+    }
+
+    // Load word frequencies to split hashtags.
+    if let Some(path) = &config.service.tokenizer.hashtag_dictionary {
+        match hashtag::dictionary()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .load(path)
+        {
+            Ok(words) => info!("Loaded {} words to split hashtags.", words),
+            Err(err) => warn!("Hashtag dictionary not loaded from {:?}: {}", path, err),
+        }
+    }
+
+    // Load lemma tables.
+    for (lang, table) in &config.service.tokenizer.lemmas {
+        let format = match table.format {
+            models::config::LemmaFormat::Tsv => TableFormat::default(),
+            models::config::LemmaFormat::Lefff => TableFormat::LEFFF,
+        };
+
+        match lemma::lemmatizer().load(lang, &table.path, format) {
+            Ok(forms) => info!("Loaded {} forms for {} lemmatization.", forms, lang),
+            Err(err) => warn!("Lemma table not loaded from {:?}: {}", table.path, err),
+        }
+    }
+
+    // Load abusive terms to flag.
+    for (lang, path) in &config.service.tokenizer.profanity {
+        match profanity::lexicon().load(lang, path) {
+            Ok(terms) => info!("Loaded {} abusive terms for {}.", terms, lang),
+            Err(err) => warn!("Profanity lexicon not loaded from {:?}: {}", path, err),
+        }
+    }
+
+    // Exclude words as they are counted, once lemma tables are loaded.
+    config.service.exclude = helpers::database::exclusions(&config, &tokenizer);
+    let config = Arc::new(config);
 
     // Set producer channel to receive expired sentences.
     let (tx, mut rx) = mpsc::channel::<Entity>(2305843009213693951);
@@ -132,33 +521,144 @@ async fn main() {
         models::config::Algorithm::Hashmap => squid_algorithm::hashtable::MapAlgorithm::default(),
     }));
 
+    // Periodically propose stop words.
+    let discovery = &config.service.stopword_discovery;
+    let frequency = discovery.enabled.then(|| {
+        Arc::new(RwLock::new(DocumentFrequency::new(
+            discovery.period,
+            discovery.periods,
+        )))
+    });
+    let candidates = Arc::new(RwLock::new(Vec::new()));
+    if let Some(frequency) = &frequency {
+        let (frequency, candidates) = (Arc::clone(frequency), Arc::clone(&candidates));
+        let (min_frequency, max_variation) =
+            (discovery.min_frequency, discovery.max_variation);
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(discovery.interval.max(1)));
+
+        tokio::task::spawn(async move {
+            loop {
+                interval.tick().await;
+
+                let found = frequency
+                    .read()
+                    .await
+                    .candidates(min_frequency, max_variation);
+                if !found.is_empty() {
+                    info!("Found {} stop word candidates.", found.len());
+                }
+                *candidates.write().await = found;
+            }
+        });
+    }
+
+    // Periodically look for spelling variants.
+    let spelling = &config.service.spelling_variants;
+    if spelling.max_distance > variants::MAX_DISTANCE {
+        warn!(
+            "Spelling variants max_distance of {} lowered to {}.",
+            spelling.max_distance,
+            variants::MAX_DISTANCE
+        );
+    }
+    let variants = spelling.enabled.then(|| Arc::new(RwLock::new(Vec::new())));
+    if let Some(variants) = &variants {
+        let (algorithm, variants) = (
+            helpers::database::Algorithm::Map(Arc::clone(&algo)),
+            Arc::clone(variants),
+        );
+        let (options, auto_merge) = (Options::from(spelling), spelling.auto_merge);
+        let aliases = spelling.aliases.clone();
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(spelling.interval.max(1)));
+
+        tokio::task::spawn(async move {
+            loop {
+                interval.tick().await;
+
+                let found =
+                    helpers::database::variants(algorithm.clone(), &options).await;
+
+                if auto_merge && !found.is_empty() {
+                    for merge in &found {
+                        helpers::database::merge(
+                            algorithm.clone(),
+                            &merge.variant,
+                            &merge.canonical,
+                        )
+                        .await;
+                    }
+                    info!("Merged {} spelling variants.", found.len());
+
+                    if let Some(path) = &aliases {
+                        if let Err(err) =
+                            helpers::database::save_aliases(algorithm.clone(), path).await
+                        {
+                            error!("Merged variants not saved to {:?}: {}", path, err);
+                        }
+                    }
+                } else if !auto_merge {
+                    if !found.is_empty() {
+                        info!("Found {} spelling variants.", found.len());
+                    }
+                    *variants.write().await = found;
+                }
+            }
+        });
+    }
+
     // Init MPSC consumer.
     let ttl_algo = Arc::clone(&algo);
+    let ttl_config = Arc::clone(&config);
     tokio::task::spawn(async move {
         while let Some(data) = rx.recv().await {
-            for word in data.post_processing_text.split_ascii_whitespace() {
-                ttl_algo.write().await.remove(word);
+            if !helpers::database::is_lang_counted(&ttl_config, &data.lang) {
+                continue;
+            }
+
+            for token in data.tokens().iter() {
+                if helpers::database::is_counted(&ttl_config, &token.key, token.kind.into()) {
+                    ttl_algo.write().await.remove(&token.key, Some(&token.display));
+                }
             }
         }
     });
 
-    // Add each words to algorithm.
+    // Merge spelling variants again before counting them.
+    if let Some(path) = &spelling.aliases {
+        match helpers::database::load_aliases(
+            helpers::database::Algorithm::Map(Arc::clone(&algo)),
+            path,
+        )
+        .await
+        {
+            Ok(aliases) => info!("Loaded {} merged spelling variants.", aliases),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+            Err(err) => warn!("Merged spelling variants not loaded from {:?}: {}", path, err),
+        }
+    }
+
+    // Add each words to algorithm, but stop words added since they were stored.
     for data in &instance.read().await.entries {
-        for str in data.post_processing_text.split_whitespace() {
-            if !config.service.exclude.contains(&str.to_string()) {
-                match config.service.message_type {
-                    models::config::MessageType::Hashtag => {
-                        if str.starts_with('#') {
-                            algo.write().await.set(str)
-                        }
-                    }
-                    models::config::MessageType::Word => {
-                        if !str.starts_with('#') {
-                            algo.write().await.set(str)
-                        }
-                    }
-                    _ => algo.write().await.set(str),
-                }
+        if !helpers::database::is_lang_counted(&config, &data.lang) {
+            continue;
+        }
+
+        let stopwords = stopwords::registry().get(&config.service.name, &data.lang);
+        for token in data.tokens().iter() {
+            let kind = token.kind.into();
+            if helpers::database::is_counted(&config, &token.key, kind)
+                && !(tokenizer.remove_stopwords && stopwords.contains(&token.key))
+            {
+                algo.write().await.add(
+                    &token.key,
+                    Occurrence {
+                        display: Some(&token.display),
+                        kind: helpers::database::counted_kind(kind),
+                        flagged: token.flagged,
+                    },
+                )
             }
         }
     }
@@ -192,7 +692,12 @@ async fn main() {
         .add_service(SquidServer::new(SuperSquid {
             algorithm: helpers::database::Algorithm::Map(algo),
             config,
+            tokenizer,
             instance,
+            frequency,
+            candidates,
+            variants,
+            learned: Mutex::new(Vec::new()),
         }))
         .serve(addr)
         .await
@@ -1,46 +1,385 @@
-use serde::Deserialize;
-
-/// The data in the configuration file for setting up Squid.
-#[derive(Deserialize, Debug)]
-pub struct Config {
-    pub port: Option<u16>,
-    pub service: Service,
-}
-
-/// The algorithm used to rank the most frequently used words.
-#[derive(Deserialize, Debug, Default)]
-pub enum Algorithm {
-    #[default]
-    Hashmap,
-}
-
-/// Which words need to be selected to be classified.
-#[derive(Deserialize, Debug, Default)]
-pub enum MessageType {
-    #[default]
-    Anything,
-    Word,
-    Hashtag,
-}
-
-/// Definition of a service. A service is equal to a database.
-#[derive(Deserialize, Debug)]
-#[allow(unused)]
-pub struct Service {
-    /// Name of the database.
-    name: String,
-    /// The algorithm to be used.
-    /// This affects RAM consumption and accuracy.
-    #[serde(default)]
-    pub algorithm: Algorithm,
-    /// The maximum number of words returned for a query.
-    max_words: Option<u8>,
-    /// What data the algorithm needs to cache.
-    #[serde(default)]
-    pub message_type: MessageType,
-    /// The language of words to be returned.
-    lang: Option<String>,
-    /// Words to exclude from the search.
-    #[serde(default)]
-    pub exclude: Vec<String>,
-}
+use serde::Deserialize;
+use squid_algorithm::variants::Options;
+use squid_tokenizer::{
+    config::{Apostrophe, TokenizerConfig},
+    link::Policy,
+    number,
+    transliterate::Scheme,
+};
+use std::{collections::HashMap, path::PathBuf};
+
+/// The data in the configuration file for setting up Squid.
+#[derive(Deserialize, Debug)]
+pub struct Config {
+    pub port: Option<u16>,
+    /// Token required by administration RPCs, such as adding stop words.
+    /// They are refused when it is not set.
+    pub admin_token: Option<String>,
+    pub service: Service,
+}
+
+/// The algorithm used to rank the most frequently used words.
+#[derive(Deserialize, Debug, Default)]
+pub enum Algorithm {
+    #[default]
+    Hashmap,
+}
+
+/// Which words need to be selected to be classified.
+#[derive(Deserialize, Debug, Default)]
+pub enum MessageType {
+    #[default]
+    Anything,
+    Word,
+    Hashtag,
+}
+
+/// How columns of a lemma table are organized.
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+pub enum LemmaFormat {
+    /// `form<TAB>lemma`.
+    #[default]
+    Tsv,
+    /// `form<TAB>category<TAB>lemma`.
+    Lefff,
+}
+
+/// What to do with URLs, mentions or e-mail addresses.
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+pub enum LinkPolicy {
+    /// Do not count them.
+    Drop,
+    /// Count them as written.
+    #[default]
+    Keep,
+    /// Only count the domain name of URLs and e-mail addresses.
+    Domain,
+}
+
+impl From<LinkPolicy> for Policy {
+    fn from(policy: LinkPolicy) -> Self {
+        match policy {
+            LinkPolicy::Drop => Policy::Drop,
+            LinkPolicy::Keep => Policy::Keep,
+            LinkPolicy::Domain => Policy::Domain,
+        }
+    }
+}
+
+/// What to do with numbers and dates.
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+pub enum NumberPolicy {
+    /// Do not count them. Hashtags, such as "#8mars", are still counted.
+    Drop,
+    /// Count them as written.
+    #[default]
+    Keep,
+    /// Count "8mars" and "8 mars", or "10k" and "10000", together.
+    Normalize,
+}
+
+impl From<NumberPolicy> for number::Policy {
+    fn from(policy: NumberPolicy) -> Self {
+        match policy {
+            NumberPolicy::Drop => number::Policy::Drop,
+            NumberPolicy::Keep => number::Policy::Keep,
+            NumberPolicy::Normalize => number::Policy::Normalize,
+        }
+    }
+}
+
+/// What to do with apostrophes inside words.
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+pub enum ApostropheHandling {
+    /// "l'IVG" gives "ivg" and "don't" gives "do" and "not".
+    #[default]
+    Elide,
+    /// "l'IVG" gives "l" and "ivg".
+    Split,
+    /// "aujourd'hui" gives "aujourdhui".
+    Remove,
+    /// "aujourd'hui" stays whole.
+    Keep,
+}
+
+impl From<ApostropheHandling> for Apostrophe {
+    fn from(apostrophe: ApostropheHandling) -> Self {
+        match apostrophe {
+            ApostropheHandling::Elide => Apostrophe::Elide,
+            ApostropheHandling::Split => Apostrophe::Split,
+            ApostropheHandling::Remove => Apostrophe::Remove,
+            ApostropheHandling::Keep => Apostrophe::Keep,
+        }
+    }
+}
+
+/// How Cyrillic and Greek are written in Latin script.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Transliteration {
+    /// ISO 9 and ISO 843, with diacritics: "ж" gives "ž".
+    Iso,
+    /// BGN/PCGN, with digraphs: "ж" gives "zh".
+    Bgn,
+}
+
+impl From<Transliteration> for Scheme {
+    fn from(transliteration: Transliteration) -> Self {
+        match transliteration {
+            Transliteration::Iso => Scheme::Iso,
+            Transliteration::Bgn => Scheme::Bgn,
+        }
+    }
+}
+
+/// Lemma table of a language.
+#[derive(Deserialize, Debug)]
+pub struct LemmaTable {
+    /// Path to the tab-separated file.
+    pub path: PathBuf,
+    /// Columns of the file.
+    #[serde(default)]
+    pub format: LemmaFormat,
+}
+
+/// Definition of a service. A service is equal to a database.
+#[derive(Deserialize, Debug)]
+#[allow(unused)]
+pub struct Service {
+    /// Name of the database.
+    pub name: String,
+    /// The algorithm to be used.
+    /// This affects RAM consumption and accuracy.
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    /// The maximum number of words returned for a query.
+    max_words: Option<u8>,
+    /// What data the algorithm needs to cache.
+    #[serde(default)]
+    pub message_type: MessageType,
+    /// The language of words to be returned, as an ISO 639-1 code (e.g. `fr`).
+    /// Texts written in other languages are saved but not counted.
+    /// Also used when the language of a text cannot be detected.
+    pub lang: Option<String>,
+    /// The language of texts whose language cannot be detected, if `lang` is
+    /// not set.
+    #[serde(default = "default_lang")]
+    pub default_lang: String,
+    /// Languages texts are expected to be written in, as ISO 639-1 codes.
+    /// Detection only chooses among them, so short texts are not mistaken
+    /// for a close language. Every language is detected when empty.
+    #[serde(default)]
+    pub langs: Vec<String>,
+    /// Words to exclude from the search.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// How texts are split into words.
+    #[serde(default)]
+    pub tokenizer: Tokenizer,
+    /// How stop words are discovered among counted texts.
+    #[serde(default)]
+    pub stopword_discovery: StopWordDiscovery,
+    /// How spelling variants of counted words are merged.
+    #[serde(default)]
+    pub spelling_variants: SpellingVariants,
+}
+
+/// Language of texts by default, to remove its stop words.
+fn default_lang() -> String {
+    "fr".to_string()
+}
+
+/// Settings of the merging of spelling variants, such as "mbape" and
+/// "mbappe" into "mbappé".
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct SpellingVariants {
+    /// Look for spelling variants among counted words.
+    pub enabled: bool,
+    /// Merge variants without review.
+    pub auto_merge: bool,
+    /// Maximum number of edited characters between a variant and the word.
+    pub max_distance: usize,
+    /// Minimum number of characters of words.
+    pub min_length: usize,
+    /// Maximum number of characters of words.
+    pub max_length: usize,
+    /// Minimum number of occurrences of the word per occurrence of the
+    /// variant.
+    pub min_ratio: f64,
+    /// Interval between two analyses, in seconds.
+    pub interval: u64,
+    /// File keeping merged variants across restarts, with a variant and its
+    /// word separated by a tab on each line.
+    pub aliases: Option<PathBuf>,
+}
+
+impl Default for SpellingVariants {
+    fn default() -> Self {
+        let options = Options::default();
+
+        SpellingVariants {
+            enabled: false,
+            auto_merge: false,
+            max_distance: options.max_distance,
+            min_length: options.min_length,
+            max_length: options.max_length,
+            min_ratio: options.min_ratio,
+            interval: 3600,
+            aliases: Some(PathBuf::from("./aliases")),
+        }
+    }
+}
+
+impl From<&SpellingVariants> for Options {
+    fn from(variants: &SpellingVariants) -> Self {
+        Options {
+            max_distance: variants.max_distance,
+            min_length: variants.min_length,
+            max_length: variants.max_length,
+            min_ratio: variants.min_ratio,
+        }
+    }
+}
+
+/// Settings of the discovery of stop words among counted texts.
+///
+/// Words found in most texts, at a steady rate over time, are proposed as
+/// stop words. Trends are found in many texts too, but not steadily.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct StopWordDiscovery {
+    /// Track words of counted texts to propose stop words.
+    pub enabled: bool,
+    /// Duration of a period, in seconds.
+    pub period: u64,
+    /// Number of periods analysed.
+    pub periods: usize,
+    /// Minimum share of texts containing a word, between 0 and 1.
+    pub min_frequency: f64,
+    /// Maximum coefficient of variation of this share between periods.
+    pub max_variation: f64,
+    /// Interval between two analyses, in seconds.
+    pub interval: u64,
+}
+
+impl Default for StopWordDiscovery {
+    fn default() -> Self {
+        StopWordDiscovery {
+            enabled: false,
+            period: 3600,
+            periods: 24,
+            min_frequency: 0.1,
+            max_variation: 0.3,
+            interval: 3600,
+        }
+    }
+}
+
+/// Settings of the tokenization pipeline of a service.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Tokenizer {
+    /// Maximum size of a text, in bytes. Longer texts are rejected.
+    pub max_bytes: usize,
+    /// Characters removed from words.
+    pub punctuation: String,
+    /// Minimum number of characters of a word.
+    pub min_length: usize,
+    /// What to do with apostrophes inside words.
+    pub apostrophe: ApostropheHandling,
+    /// Custom stop words file, with one word per line.
+    pub stopwords: Option<PathBuf>,
+    /// Remove stop words before counting.
+    pub remove_stopwords: bool,
+    /// Collapse elongated letters, so "loooool" is counted as "lol".
+    pub collapse_repeats: bool,
+    /// Replace leetspeak, so "m4cron" is counted as "macron".
+    pub fold_leet: bool,
+    /// Abusive terms by language, one per line, to flag texts containing
+    /// them. Flagged words are still counted.
+    pub profanity: HashMap<String, PathBuf>,
+    /// Count words with and without diacritics together.
+    /// "journée" and "journee" would then share the same counter.
+    pub fold_accents: bool,
+    /// Count Cyrillic and Greek words in Latin script.
+    /// "Зеленский" and "zelenskiy" would then share the same counter.
+    pub transliterate: Option<Transliteration>,
+    /// Reduce words to their stem before counting them.
+    /// "manifestation" and "manifestations" would then share the same counter.
+    pub stem: bool,
+    /// Lemma tables by language, to replace words by their lemma before
+    /// counting them. "allons" and "vont" would then both be counted as "aller".
+    pub lemmas: HashMap<String, LemmaTable>,
+    /// Also count words of hashtags.
+    /// "#JourneeDesDroitsDesFemmes" would then count for "droits" and "femmes".
+    pub segment_hashtags: bool,
+    /// Word frequency list used to split lowercase hashtags, such as
+    /// "#soutienayanakamura". Words of added texts are learned anyway.
+    pub hashtag_dictionary: Option<PathBuf>,
+    /// Also count proper-noun phrases, such as "Aya Nakamura".
+    pub phrases: bool,
+    /// What to do with numbers and dates, such as "2024", "1er" or "8mars".
+    pub numbers: NumberPolicy,
+    /// What to do with links. Keeping only their domain name ranks the most
+    /// linked websites.
+    pub urls: LinkPolicy,
+    /// What to do with mentions, such as "@gravitalia".
+    pub mentions: LinkPolicy,
+    /// What to do with e-mail addresses.
+    pub emails: LinkPolicy,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        let config = TokenizerConfig::default();
+
+        Tokenizer {
+            max_bytes: config.max_bytes,
+            punctuation: config.punctuation.iter().collect(),
+            min_length: config.min_length,
+            apostrophe: ApostropheHandling::default(),
+            stopwords: Some(PathBuf::from("./stopwords")),
+            remove_stopwords: config.remove_stopwords,
+            collapse_repeats: config.collapse_repeats,
+            fold_leet: config.fold_leet,
+            profanity: HashMap::new(),
+            fold_accents: config.fold_accents,
+            transliterate: None,
+            stem: config.stem,
+            lemmas: HashMap::new(),
+            segment_hashtags: config.segment_hashtags,
+            hashtag_dictionary: None,
+            phrases: config.phrases,
+            numbers: NumberPolicy::default(),
+            urls: LinkPolicy::default(),
+            mentions: LinkPolicy::default(),
+            emails: LinkPolicy::default(),
+        }
+    }
+}
+
+impl From<&Tokenizer> for TokenizerConfig {
+    fn from(tokenizer: &Tokenizer) -> Self {
+        TokenizerConfig {
+            max_bytes: tokenizer.max_bytes,
+            punctuation: tokenizer.punctuation.chars().collect(),
+            min_length: tokenizer.min_length,
+            apostrophe: tokenizer.apostrophe.into(),
+            stopwords: tokenizer.stopwords.clone(),
+            remove_stopwords: tokenizer.remove_stopwords,
+            collapse_repeats: tokenizer.collapse_repeats,
+            fold_leet: tokenizer.fold_leet,
+            flag_profanity: !tokenizer.profanity.is_empty(),
+            fold_accents: tokenizer.fold_accents,
+            transliterate: tokenizer.transliterate.map(Scheme::from),
+            lemmatize: !tokenizer.lemmas.is_empty(),
+            stem: tokenizer.stem,
+            segment_hashtags: tokenizer.segment_hashtags,
+            phrases: tokenizer.phrases,
+            numbers: tokenizer.numbers.into(),
+            urls: tokenizer.urls.into(),
+            mentions: tokenizer.mentions.into(),
+            emails: tokenizer.emails.into(),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use regex_lite::Regex;
 use serde::{Deserialize, Serialize};
 use squid_db::Attributes;
+use squid_tokenizer::{normalize::unescape_legacy, profanity, TokenKind};
+use std::borrow::Cow;
 
 lazy_static! {
     static ref EXPIRE_AT: Regex = Regex::new(r"expire_at:(\d+)").unwrap();
@@ -23,11 +25,83 @@ pub struct Entity {
     /// Accepted metatag:
     /// - `expire_at:<u64>` as TTL. 0 means infinite.
     /// - `tag:<String>` to specify a field for the sentence.
+    /// - `flagged` if the text contains abusive terms.
     ///
     /// # Examples
     /// `expire_at:0,tag:politic`,
-    /// `expire_at:1714240000,tag:sport`
+    /// `expire_at:1714240000,tag:sport,flagged`
     pub meta: String,
+    /// Tokens of the text, in the order of `post_processing_text`.
+    pub tokens: Vec<StoredToken>,
+}
+
+/// Entity as saved before its tokens were stored.
+#[derive(Deserialize)]
+struct LegacyEntity {
+    id: String,
+    original_text: Option<String>,
+    post_processing_text: String,
+    lang: String,
+    meta: String,
+}
+
+/// Token of a text, as counted by the algorithm.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct StoredToken {
+    /// Key counted by the algorithm.
+    pub key: String,
+    /// Spelling of the token in the original text.
+    pub display: String,
+    /// What the token represents.
+    pub kind: StoredKind,
+    /// Whether the token is an abusive term.
+    pub flagged: bool,
+}
+
+/// Mirror of [`TokenKind`] to save it.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StoredKind {
+    Word,
+    Hashtag,
+    Mention,
+    Url,
+    Email,
+    Emoji,
+    Number,
+    Date,
+    Phrase,
+}
+
+impl From<TokenKind> for StoredKind {
+    fn from(kind: TokenKind) -> Self {
+        match kind {
+            TokenKind::Word => StoredKind::Word,
+            TokenKind::Hashtag => StoredKind::Hashtag,
+            TokenKind::Mention => StoredKind::Mention,
+            TokenKind::Url => StoredKind::Url,
+            TokenKind::Email => StoredKind::Email,
+            TokenKind::Emoji => StoredKind::Emoji,
+            TokenKind::Number => StoredKind::Number,
+            TokenKind::Date => StoredKind::Date,
+            TokenKind::Phrase => StoredKind::Phrase,
+        }
+    }
+}
+
+impl From<StoredKind> for TokenKind {
+    fn from(kind: StoredKind) -> Self {
+        match kind {
+            StoredKind::Word => TokenKind::Word,
+            StoredKind::Hashtag => TokenKind::Hashtag,
+            StoredKind::Mention => TokenKind::Mention,
+            StoredKind::Url => TokenKind::Url,
+            StoredKind::Email => TokenKind::Email,
+            StoredKind::Emoji => TokenKind::Emoji,
+            StoredKind::Number => TokenKind::Number,
+            StoredKind::Date => TokenKind::Date,
+            StoredKind::Phrase => TokenKind::Phrase,
+        }
+    }
 }
 
 impl Attributes for Entity {
@@ -41,4 +115,50 @@ impl Attributes for Entity {
             .and_then(|capture| capture.get(1))
             .map(|expire| expire.as_str().parse().unwrap_or_default())
     }
+
+    fn upgrade(record: &[u8]) -> Option<Self> {
+        let legacy: LegacyEntity = bincode::deserialize(record).ok()?;
+
+        Some(Entity {
+            id: legacy.id,
+            original_text: legacy.original_text,
+            post_processing_text: legacy.post_processing_text,
+            lang: legacy.lang,
+            meta: legacy.meta,
+            tokens: Vec::new(),
+        })
+    }
+}
+
+impl Entity {
+    /// Returns the post-processed text as genuine UTF-8.
+    ///
+    /// Entities saved before Unicode normalization store non-ASCII characters
+    /// as `\u{e9}` escape sequences. They are decoded on the fly so counters
+    /// built from old and new entities share the same keys.
+    pub fn normalized_text(&self) -> Cow<'_, str> {
+        unescape_legacy(&self.post_processing_text)
+    }
+
+    /// Returns the tokens of the text.
+    ///
+    /// Entities saved before tokens were stored only have their keys, whose
+    /// kind and flag are guessed, and which are displayed as is.
+    pub fn tokens(&self) -> Cow<'_, [StoredToken]> {
+        if !self.tokens.is_empty() {
+            return Cow::Borrowed(&self.tokens);
+        }
+
+        Cow::Owned(
+            self.normalized_text()
+                .split_whitespace()
+                .map(|key| StoredToken {
+                    key: key.to_string(),
+                    display: key.replace(crate::helpers::database::SPACE, " "),
+                    kind: crate::helpers::database::kind(key).into(),
+                    flagged: profanity::lexicon().matches(key, &self.lang),
+                })
+                .collect(),
+        )
+    }
 }